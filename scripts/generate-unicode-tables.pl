@@ -0,0 +1,188 @@
+#!/usr/bin/env perl
+# Unicode のプロパティ表を src/unicode/tables に生成する
+#
+#   $ perl scripts/generate-unicode-tables.pl src/unicode/tables
+#
+# 表は Perl に同梱されている Unicode::UCD のデータから作るため、生成にネットワークは不要
+use strict;
+use warnings;
+use Unicode::UCD qw(prop_aliases prop_invlist prop_value_aliases prop_values);
+
+my $dir = shift @ARGV or die "usage: $0 <output directory>\n";
+my $version = Unicode::UCD::UnicodeVersion();
+
+# 反転リストを、サロゲートを除いた [start, end] の範囲の列に変換する
+sub ranges_of {
+    my @invlist = @_;
+    push @invlist, 0x110000 if @invlist % 2;
+
+    my @ranges;
+    for (my $i = 0; $i < @invlist; $i += 2) {
+        my ($start, $end) = ($invlist[$i], $invlist[$i + 1] - 1);
+        if ($start < 0xD800 && $end >= 0xD800) {
+            push @ranges, [$start, 0xD7FF];
+            $start = 0xE000;
+        }
+        $start = 0xE000 if $start >= 0xD800 && $start <= 0xDFFF;
+        $end = 0xD7FF if $end >= 0xD800 && $end <= 0xDFFF;
+        push @ranges, [$start, $end] if $start <= $end;
+    }
+    return @ranges;
+}
+
+sub property_ranges {
+    my ($name) = @_;
+    my @invlist = prop_invlist($name);
+    die "unknown property: $name\n" unless @invlist;
+    return ranges_of(@invlist);
+}
+
+sub format_table {
+    my ($name, @ranges) = @_;
+    my $out = "pub const $name: &[(char, char)] = &[\n";
+    for my $range (@ranges) {
+        $out .= sprintf("    ('\\u{%X}', '\\u{%X}'),\n", @$range);
+    }
+    return $out . "];\n";
+}
+
+# 名前の照合では大文字・小文字、空白、'_'、'-' を区別しない (UAX44-LM3)
+sub normalize {
+    my ($name) = @_;
+    $name = lc $name;
+    $name =~ s/[ _-]//g;
+    return $name;
+}
+
+sub const_name {
+    my ($name) = @_;
+    return uc $name;
+}
+
+# 別名を含む正規化した名前から表を引けるようにする
+sub format_by_name {
+    my (%tables) = @_;
+    my $out = "pub const BY_NAME: &[(&str, &[(char, char)])] = &[\n";
+    for my $name (sort keys %tables) {
+        $out .= sprintf("    (\"%s\", %s),\n", $name, $tables{$name});
+    }
+    return $out . "];\n";
+}
+
+sub add_names {
+    my ($by_name, $const, @names) = @_;
+    for my $name (map { normalize($_) } @names) {
+        next if exists $by_name->{$name} && $by_name->{$name} eq $const;
+        die "duplicate name: $name\n" if exists $by_name->{$name};
+        $by_name->{$name} = $const;
+    }
+}
+
+sub write_file {
+    my ($file, @tables) = @_;
+    my $path = "$dir/$file";
+    open my $fh, '>', $path or die "$path: $!\n";
+    print $fh "// 自動生成されたファイルのため編集しないこと\n";
+    print $fh "// scripts/generate-unicode-tables.pl (Unicode $version)\n\n";
+    print $fh join("\n", @tables);
+    close $fh;
+
+    system('rustfmt', '--edition', '2021', $path) == 0 or die "rustfmt failed: $path\n";
+}
+
+# \d, \w, \s の Unicode 版の定義 (UTS#18 Annex C)
+write_file(
+    'perl.rs',
+    format_table('DECIMAL_NUMBER', property_ranges('Digit')),
+    format_table('WORD', property_ranges('Word')),
+    format_table('WHITE_SPACE', property_ranges('White_Space')),
+);
+
+# 一般カテゴリ (\p{Lu}, \p{Letter} など)
+{
+    my (%by_name, @tables);
+    for my $value (prop_values('gc')) {
+        my @aliases = prop_value_aliases('gc', $value);
+        my $const = const_name($aliases[1]);
+        push @tables, format_table($const, property_ranges("gc=$value"));
+        add_names(\%by_name, $const, @aliases);
+    }
+    write_file('general_category.rs', format_by_name(%by_name), @tables);
+}
+
+# 用字 (\p{Greek}, \p{sc=Grek} など)
+{
+    my (%by_name, @tables);
+    for my $value (sort(prop_values('sc'))) {
+        my @aliases = prop_value_aliases('sc', $value);
+        # Katakana_Or_Hiragana は Script_Extensions でのみ使われる
+        next unless @aliases;
+        my $const = const_name($aliases[1]);
+        push @tables, format_table($const, property_ranges("sc=$value"));
+        add_names(\%by_name, $const, @aliases);
+    }
+    write_file('script.rs', format_by_name(%by_name), @tables);
+}
+
+# 2値プロパティ (\p{Alphabetic}, \p{Emoji} など)
+{
+    my @properties = qw(
+        Alphabetic ASCII_Hex_Digit Bidi_Control Bidi_Mirrored Case_Ignorable Cased
+        Changes_When_Casefolded Changes_When_Casemapped Changes_When_Lowercased
+        Changes_When_Titlecased Changes_When_Uppercased Dash Default_Ignorable_Code_Point
+        Deprecated Diacritic Emoji Emoji_Component Emoji_Modifier Emoji_Modifier_Base
+        Emoji_Presentation Extended_Pictographic Extender Grapheme_Base Grapheme_Extend
+        Hex_Digit ID_Continue ID_Start Ideographic IDS_Binary_Operator IDS_Trinary_Operator
+        Join_Control Logical_Order_Exception Lowercase Math Noncharacter_Code_Point
+        Pattern_Syntax Pattern_White_Space Prepended_Concatenation_Mark Quotation_Mark
+        Radical Regional_Indicator Sentence_Terminal Soft_Dotted Terminal_Punctuation
+        Unified_Ideograph Uppercase Variation_Selector White_Space XID_Continue XID_Start
+    );
+
+    my (%by_name, @tables);
+    for my $property (@properties) {
+        my @aliases = prop_aliases($property);
+        my $const = const_name($aliases[1]);
+        push @tables, format_table($const, property_ranges($property));
+        add_names(\%by_name, $const, @aliases);
+    }
+
+    # UTS#18 で定められた特別なプロパティ
+    push @tables, format_table('ANY', [0, 0xD7FF], [0xE000, 0x10FFFF]);
+    add_names(\%by_name, 'ANY', 'Any');
+    push @tables, format_table('ASCII', [0, 0x7F]);
+    add_names(\%by_name, 'ASCII', 'ASCII');
+    push @tables, format_table('ASSIGNED', property_ranges('Assigned'));
+    add_names(\%by_name, 'ASSIGNED', 'Assigned');
+
+    write_file('property_bool.rs', format_by_name(%by_name), @tables);
+}
+
+# 単純ケースフォールディング (CaseFolding.txt の C と S)。
+# 同一視される文字をまとめ、各文字からそれ以外の文字を引けるようにする
+{
+    my $casefolds = Unicode::UCD::all_casefolds();
+    my %orbits;
+    for my $code (keys %$casefolds) {
+        my $fold = $casefolds->{$code};
+        next unless $fold->{status} eq 'C' || $fold->{status} eq 'S';
+        my $target = hex $fold->{simple};
+        $orbits{$target}{$target} = 1;
+        $orbits{$target}{$code} = 1;
+    }
+
+    my %variants;
+    for my $orbit (values %orbits) {
+        my @chars = sort { $a <=> $b } keys %$orbit;
+        for my $c (@chars) {
+            $variants{$c} = [grep { $_ != $c } @chars];
+        }
+    }
+
+    my $out = "pub const CASE_FOLDING_SIMPLE: &[(char, &[char])] = &[\n";
+    for my $c (sort { $a <=> $b } keys %variants) {
+        my $chars = join(', ', map { sprintf("'\\u{%X}'", $_) } @{$variants{$c}});
+        $out .= sprintf("    ('\\u{%X}', &[%s]),\n", $c, $chars);
+    }
+    write_file('case_folding.rs', $out . "];\n");
+}
@@ -1,17 +1,39 @@
-use std::{
-    iter::{Map, Peekable},
-    str::Chars,
+use super::{
+    Assertion, ClassSetOpKind, Flags, LookaroundKind, PerlClass, PerlClassKind, Position,
+    PosixClass, PosixClassKind, Span, Spanned, UnicodeClass,
 };
+use crate::error::Error;
+use crate::unicode;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
-    Character(char),   // a, b, c, ...
-    AlternateOperator, // |
-    StarOperator,      // *
-    PlusOperator,      // +
-    QuestionOperator,  // ?
-    OpenParenthesis,   // (
-    CloseParenthesis,  // )
+    Character(char),                      // a, b, c, ...
+    AlternateOperator,                    // |
+    StarOperator,                         // *
+    PlusOperator,                         // +
+    QuestionOperator,                     // ?
+    Dot,                                  // .
+    RepetitionOperator(u32, Option<u32>), // {n}, {n,}, {n,m}
+    PerlClass(PerlClass),                 // \d, \W
+    UnicodeClass(UnicodeClass),           // \pL, \p{Greek}
+    PosixClass(PosixClass),               // [:alpha:] (文字クラスの中のみ)
+    OpenParenthesis,                      // (
+    OpenNamedGroup(String),               // (?P<name>, (?<name>, (?'name'
+    OpenGroup(Flags),                     // (?:, (?i:
+    OpenLookaround(LookaroundKind),       // (?=, (?!, (?<=, (?<!
+    OpenAtomicGroup,                      // (?>
+    Backreference(u32),                   // \1
+    NamedBackreference(String),           // \k<name>
+    SetFlags(Flags),                      // (?i)
+    CloseParenthesis,                     // )
+    OpenBracket,                          // [
+    OpenNegatedBracket,                   // [^
+    CloseBracket,                         // ] (文字クラスの中のみ)
+    RangeOperator,                        // - (文字クラスの中のみ)
+    ClassSetOperator(ClassSetOpKind),     // &&, --, ~~ (文字クラスの中のみ)
+    StartAnchor,                          // ^
+    Assertion(Assertion),                 // \b, \B, \<, \>, \A, \z, \Z
+    EndAnchor,                            // $
     EndOfFile,
 }
 
@@ -22,94 +44,1072 @@ impl From<char> for Token {
             '*' => Token::StarOperator,
             '+' => Token::PlusOperator,
             '?' => Token::QuestionOperator,
+            '.' => Token::Dot,
             '(' => Token::OpenParenthesis,
             ')' => Token::CloseParenthesis,
+            '[' => Token::OpenBracket,
+            '^' => Token::StartAnchor,
+            '$' => Token::EndAnchor,
             _ => Token::Character(value),
         }
     }
 }
 
-impl From<Option<Token>> for Token {
-    fn from(value: Option<Token>) -> Self {
-        value.unwrap_or(Token::EndOfFile)
-    }
-}
-
-type TokenPeekable<'a> = Peekable<Map<Chars<'a>, fn(char) -> Token>>;
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lexer<'a> {
-    token_iter: TokenPeekable<'a>,
+    input: &'a str,
+    position: usize,
+    ignore_whitespace: bool,
+    in_class: bool,
+    // \Q から \E までの間か
+    quoted: bool,
+    // 行と列を求めるために、lines_scanned までに現れた行の数と最後の行の開始位置を覚えておく
+    line: usize,
+    line_start: usize,
+    lines_scanned: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Lexer {
-            token_iter: input
-                .chars()
-                .map(Into::into as fn(char) -> Token)
-                .peekable(),
+            input,
+            position: 0,
+            ignore_whitespace: false,
+            in_class: false,
+            quoted: false,
+            line: 1,
+            line_start: 0,
+            lines_scanned: 0,
+        }
+    }
+
+    // x フラグが有効な間は空白と # から行末までのコメントを読み飛ばす
+    pub fn set_ignore_whitespace(&mut self, ignore_whitespace: bool) {
+        self.ignore_whitespace = ignore_whitespace;
+    }
+
+    pub fn ignore_whitespace(&self) -> bool {
+        self.ignore_whitespace
+    }
+
+    // 文字クラスの中ではメタ文字の扱いが変わる
+    pub fn set_in_class(&mut self, in_class: bool) {
+        self.in_class = in_class;
+    }
+
+    pub fn peek_token(&self) -> Result<Token, Error> {
+        self.clone().next_token()
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, Error> {
+        self.scan_token().map(|(token, _)| token)
+    }
+
+    // 次のトークンと、パターン中でそのトークンが占める範囲を返す
+    pub fn next_token_with_span(&mut self) -> Result<Spanned<Token>, Error> {
+        let (token, start) = self.scan_token()?;
+        let start = self.position_at(start);
+        let end = self.position_at(self.position);
+
+        Ok(Spanned::new(token, Span::new(start, end)))
+    }
+
+    // 次のトークンとその開始位置を返す
+    fn scan_token(&mut self) -> Result<(Token, usize), Error> {
+        // \Q から \E までの文字はすべて文字として扱う
+        if self.quoted {
+            if self.input[self.position..].starts_with(r"\E") {
+                self.position += 2;
+                self.quoted = false;
+            } else {
+                let start = self.position;
+                if let Some(c) = self.next_char() {
+                    return Ok((Token::Character(c), start));
+                }
+            }
+        }
+
+        if self.ignore_whitespace && !self.in_class {
+            self.skip_whitespace();
+        }
+
+        let start = self.position;
+        let Some(c) = self.next_char() else {
+            return Ok((Token::EndOfFile, start));
+        };
+
+        let token = match c {
+            '\\' => self.scan_escape(),
+            ']' if self.in_class => Ok(Token::CloseBracket),
+            '&' | '-' | '~' if self.in_class && self.peek_char() == Some(c) => {
+                self.next_char();
+                Ok(Token::ClassSetOperator(match c {
+                    '&' => ClassSetOpKind::Intersection,
+                    '-' => ClassSetOpKind::Difference,
+                    _ => ClassSetOpKind::SymmetricDifference,
+                }))
+            }
+            '-' if self.in_class => Ok(Token::RangeOperator),
+            '[' if self.in_class => self.scan_nested_class(),
+            _ if self.in_class => Ok(Token::Character(c)),
+            '[' if self.peek_char() == Some('^') => {
+                self.next_char();
+                Ok(Token::OpenNegatedBracket)
+            }
+            '(' if self.peek_char() == Some('?') => {
+                self.next_char();
+                self.scan_group()
+            }
+            '{' => self.scan_repetition(),
+            _ => Ok(c.into()),
+        };
+
+        // 位置を持たないエラーはトークンの開始位置を指す
+        token
+            .map(|token| (token, start))
+            .map_err(|error| error.at(start))
+    }
+
+    // offset の行と列を求める。行の数え上げは前回求めた位置から続ける
+    fn position_at(&mut self, offset: usize) -> Position {
+        if offset < self.lines_scanned {
+            (self.line, self.line_start, self.lines_scanned) = (1, 0, 0);
+        }
+        for (i, c) in self.input[self.lines_scanned..offset].char_indices() {
+            if c == '\n' {
+                self.line += 1;
+                self.line_start = self.lines_scanned + i + 1;
+            }
+        }
+        self.lines_scanned = offset;
+
+        let column = self.input[self.line_start..offset].chars().count() + 1;
+        Position::new(offset, self.line, column)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    // '\\' の直後からエスケープシーケンスを読む
+    fn scan_escape(&mut self) -> Result<Token, Error> {
+        let c = self
+            .next_char()
+            .ok_or(Error::syntax("Incomplete escape sequence"))?;
+
+        let literal = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            'a' => '\x07',
+            'e' => '\x1B',
+            '0' => self.scan_octal()?,
+            'o' => self.scan_braced_code_point(8)?,
+            'x' if self.peek_char() == Some('{') => self.scan_braced_code_point(16)?,
+            'x' => self.scan_code_point(2)?,
+            'u' if self.peek_char() == Some('{') => self.scan_braced_code_point(16)?,
+            'u' => self.scan_code_point(4)?,
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => {
+                let kind = match c.to_ascii_lowercase() {
+                    'd' => PerlClassKind::Digit,
+                    'w' => PerlClassKind::Word,
+                    _ => PerlClassKind::Space,
+                };
+                return Ok(Token::PerlClass(PerlClass {
+                    kind,
+                    negated: c.is_ascii_uppercase(),
+                }));
+            }
+            'p' | 'P' => return self.scan_unicode_class(c == 'P'),
+            'b' => return self.scan_word_boundary(),
+            'B' => return Ok(Token::Assertion(Assertion::NotWordBoundary)),
+            'Q' => {
+                self.quoted = true;
+                return self.next_token();
+            }
+            'A' => return Ok(Token::Assertion(Assertion::StartText)),
+            'z' => return Ok(Token::Assertion(Assertion::EndText)),
+            'Z' => return Ok(Token::Assertion(Assertion::EndTextOptionalNewline)),
+            '1'..='9' if !self.in_class => return self.scan_backreference(),
+            'k' if !self.in_class => return self.scan_named_backreference(),
+            // 文字クラスの中では文字として扱う
+            '<' if !self.in_class => return Ok(Token::Assertion(Assertion::WordStart)),
+            '>' if !self.in_class => return Ok(Token::Assertion(Assertion::WordEnd)),
+            c if is_escapable_character(c) => c,
+            _ => return Err(Error::syntax("Unknown escape sequence")),
+        };
+
+        Ok(Token::Character(literal))
+    }
+
+    // \ の直後の1文字目から続く数字をグループ番号として読む
+    fn scan_backreference(&mut self) -> Result<Token, Error> {
+        let start = self.position - 1;
+        while self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+            self.next_char();
+        }
+
+        self.input[start..self.position]
+            .parse()
+            .map(Token::Backreference)
+            .map_err(|_| Error::syntax("Invalid backreference"))
+    }
+
+    // \k の直後から <name> を読む
+    fn scan_named_backreference(&mut self) -> Result<Token, Error> {
+        if self.next_char() != Some('<') {
+            return Err(Error::syntax("Expected '<' after \\k"));
+        }
+        let name = self.scan_name('>')?;
+
+        Ok(Token::NamedBackreference(name))
+    }
+
+    // \b の直後から \b{start}, \b{end} の {...} を読む
+    fn scan_word_boundary(&mut self) -> Result<Token, Error> {
+        let rest = &self.input[self.position..];
+        let Some(name) = rest.strip_prefix('{').and_then(|rest| rest.split_once('}')) else {
+            return Ok(Token::Assertion(Assertion::WordBoundary));
+        };
+
+        let assertion = match name.0 {
+            "start" => Assertion::WordStart,
+            "end" => Assertion::WordEnd,
+            // \b{2} などは繰り返しとして扱う
+            name if !name.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                return Ok(Token::Assertion(Assertion::WordBoundary))
+            }
+            _ => return Err(Error::syntax("Unknown word boundary")),
+        };
+        self.position += name.0.len() + 2;
+
+        Ok(Token::Assertion(assertion))
+    }
+
+    // \p, \P の直後から、\pL のような1文字の名前か {...} で囲まれた名前を読む
+    fn scan_unicode_class(&mut self, negated: bool) -> Result<Token, Error> {
+        let name = match self.next_char() {
+            Some('{') => {
+                let start = self.position;
+                loop {
+                    match self.next_char() {
+                        Some('}') => break,
+                        Some(_) => {}
+                        None => return Err(Error::syntax("Unicode class is not closed")),
+                    }
+                }
+                self.input[start..self.position - 1].to_string()
+            }
+            Some(c) if c.is_ascii_alphabetic() => c.to_string(),
+            _ => return Err(Error::syntax("Incomplete escape sequence")),
+        };
+
+        let (name, value) = match name.split_once(['=', ':']) {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (name, None),
+        };
+        // 存在しないプロパティはこの時点でエラーにする
+        unicode::property(&name, value.as_deref())?;
+
+        Ok(Token::UnicodeClass(UnicodeClass {
+            name,
+            value,
+            negated,
+        }))
+    }
+
+    // 文字クラスの中の '[' は [:alpha:] または入れ子の文字クラスの始まり
+    fn scan_nested_class(&mut self) -> Result<Token, Error> {
+        if self.peek_char() == Some(':') {
+            if let Some(token) = self.scan_posix_class()? {
+                return Ok(token);
+            }
+        }
+        if self.peek_char() == Some('^') {
+            self.next_char();
+            return Ok(Token::OpenNegatedBracket);
+        }
+        Ok(Token::OpenBracket)
+    }
+
+    // '[' の直後から [:name:] を読む。形式に合わない場合は None を返す
+    fn scan_posix_class(&mut self) -> Result<Option<Token>, Error> {
+        let rest = &self.input[self.position + 1..];
+        let Some(end) = rest.find(":]") else {
+            return Ok(None);
+        };
+
+        let (negated, name) = match rest[..end].strip_prefix('^') {
+            Some(name) => (true, name),
+            None => (false, &rest[..end]),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Ok(None);
+        }
+
+        let kind = match name {
+            "alnum" => PosixClassKind::Alnum,
+            "alpha" => PosixClassKind::Alpha,
+            "ascii" => PosixClassKind::Ascii,
+            "blank" => PosixClassKind::Blank,
+            "cntrl" => PosixClassKind::Cntrl,
+            "digit" => PosixClassKind::Digit,
+            "graph" => PosixClassKind::Graph,
+            "lower" => PosixClassKind::Lower,
+            "print" => PosixClassKind::Print,
+            "punct" => PosixClassKind::Punct,
+            "space" => PosixClassKind::Space,
+            "upper" => PosixClassKind::Upper,
+            "word" => PosixClassKind::Word,
+            "xdigit" => PosixClassKind::Xdigit,
+            _ => return Err(Error::syntax("Unknown POSIX class")),
+        };
+
+        // ':' + 名前 + ":]"
+        self.position += 1 + end + 2;
+        Ok(Some(Token::PosixClass(PosixClass { kind, negated })))
+    }
+
+    // \0 に続く高々2桁の8進数を読む
+    fn scan_octal(&mut self) -> Result<char, Error> {
+        let mut value = 0;
+        for _ in 0..2 {
+            match self.peek_char().and_then(|c| c.to_digit(8)) {
+                Some(digit) => {
+                    self.next_char();
+                    value = value * 8 + digit;
+                }
+                None => break,
+            }
+        }
+        char::from_u32(value).ok_or(Error::syntax("Invalid code point"))
+    }
+
+    // 固定桁数の16進数を読む
+    fn scan_code_point(&mut self, digits: usize) -> Result<char, Error> {
+        let mut value = 0;
+        for _ in 0..digits {
+            let digit = self
+                .next_char()
+                .and_then(|c| c.to_digit(16))
+                .ok_or(Error::syntax("Incomplete escape sequence"))?;
+            value = value * 16 + digit;
+        }
+        char::from_u32(value).ok_or(Error::syntax("Invalid code point"))
+    }
+
+    // {...} で囲まれた任意桁数の数値を読む
+    fn scan_braced_code_point(&mut self, radix: u32) -> Result<char, Error> {
+        if self.next_char() != Some('{') {
+            return Err(Error::syntax("Expected '{' in escape sequence"));
+        }
+
+        let start = self.position;
+        loop {
+            match self.next_char() {
+                Some('}') => break,
+                Some(c) if c.is_digit(radix) => {}
+                Some(_) => return Err(Error::syntax("Invalid digit in escape sequence")),
+                None => return Err(Error::syntax("Incomplete escape sequence")),
+            }
+        }
+
+        let digits = &self.input[start..self.position - 1];
+        u32::from_str_radix(digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(Error::syntax("Invalid code point"))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek_char() {
+            if c.is_whitespace() {
+                self.next_char();
+            } else if c == '#' {
+                while !matches!(self.next_char(), Some('\n') | None) {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    // '{' の直後から繰り返し回数を読む。形式に合わない場合は '{' を文字として扱う
+    fn scan_repetition(&mut self) -> Result<Token, Error> {
+        let start = self.position;
+        self.skip_repetition_whitespace();
+        let Some(min) = self.scan_decimal()? else {
+            self.position = start;
+            return Ok(Token::Character('{'));
+        };
+
+        self.skip_repetition_whitespace();
+        let max = match self.next_char() {
+            Some('}') => Some(min),
+            Some(',') => {
+                self.skip_repetition_whitespace();
+                let max = self.scan_decimal()?;
+                self.skip_repetition_whitespace();
+                if self.next_char() != Some('}') {
+                    self.position = start;
+                    return Ok(Token::Character('{'));
+                }
+                max
+            }
+            _ => {
+                self.position = start;
+                return Ok(Token::Character('{'));
+            }
+        };
+
+        if max.is_some_and(|max| max < min) {
+            return Err(Error::syntax("Invalid repetition range"));
+        }
+
+        Ok(Token::RepetitionOperator(min, max))
+    }
+
+    // x フラグが有効な場合は {n,m} の各部分の間にも空白とコメントを書ける
+    fn skip_repetition_whitespace(&mut self) {
+        if self.ignore_whitespace {
+            self.skip_whitespace();
+        }
+    }
+
+    fn scan_decimal(&mut self) -> Result<Option<u32>, Error> {
+        let start = self.position;
+        while self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+            self.next_char();
+        }
+
+        if start == self.position {
+            return Ok(None);
+        }
+        self.input[start..self.position]
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::syntax("Repetition count is too large"))
+    }
+
+    // "(?" の直後から拡張されたグループの構文を読む
+    fn scan_group(&mut self) -> Result<Token, Error> {
+        match self.peek_char() {
+            Some('P') => {
+                self.next_char();
+                if self.next_char() != Some('<') {
+                    return Err(Error::syntax("Unknown group syntax"));
+                }
+                self.scan_group_name('>')
+            }
+            Some('=') => {
+                self.next_char();
+                Ok(Token::OpenLookaround(LookaroundKind::PositiveLookahead))
+            }
+            Some('>') => {
+                self.next_char();
+                Ok(Token::OpenAtomicGroup)
+            }
+            Some('!') => {
+                self.next_char();
+                Ok(Token::OpenLookaround(LookaroundKind::NegativeLookahead))
+            }
+            Some('<') => {
+                self.next_char();
+                match self.peek_char() {
+                    Some('=') => {
+                        self.next_char();
+                        Ok(Token::OpenLookaround(LookaroundKind::PositiveLookbehind))
+                    }
+                    Some('!') => {
+                        self.next_char();
+                        Ok(Token::OpenLookaround(LookaroundKind::NegativeLookbehind))
+                    }
+                    _ => self.scan_group_name('>'),
+                }
+            }
+            Some('\'') => {
+                self.next_char();
+                self.scan_group_name('\'')
+            }
+            _ => self.scan_flags(),
+        }
+    }
+
+    // (?imsx-imsx) または (?imsx-imsx: の形式のフラグを読む
+    fn scan_flags(&mut self) -> Result<Token, Error> {
+        let mut flags = Flags::default();
+        let mut enable = true;
+        let mut is_empty = true;
+
+        loop {
+            let c = self
+                .next_char()
+                .ok_or(Error::syntax("Flag group is not closed"))?;
+            let flag = match c {
+                ':' | ')' => {
+                    if !enable && is_empty {
+                        return Err(Error::syntax("Expected a flag after '-'"));
+                    }
+                    if c == ':' {
+                        return Ok(Token::OpenGroup(flags));
+                    }
+                    if flags == Flags::default() {
+                        return Err(Error::syntax("Empty flag group"));
+                    }
+                    return Ok(Token::SetFlags(flags));
+                }
+                '-' if enable => {
+                    enable = false;
+                    is_empty = true;
+                    continue;
+                }
+                'i' => &mut flags.case_insensitive,
+                'm' => &mut flags.multi_line,
+                's' => &mut flags.dot_matches_new_line,
+                'x' => &mut flags.ignore_whitespace,
+                'u' => &mut flags.unicode,
+                'R' => &mut flags.crlf,
+                _ => return Err(Error::syntax("Unknown flag")),
+            };
+
+            if flag.is_some() {
+                return Err(Error::syntax("Duplicate flag"));
+            }
+            *flag = Some(enable);
+            is_empty = false;
         }
     }
 
-    pub fn peek_token(&mut self) -> Token {
-        self.token_iter.peek().cloned().into()
+    fn scan_group_name(&mut self, terminator: char) -> Result<Token, Error> {
+        let name = self.scan_name(terminator)?;
+        Ok(Token::OpenNamedGroup(name))
     }
 
-    pub fn next_token(&mut self) -> Token {
-        self.token_iter.next().into()
+    // terminator までをグループ名として読む
+    fn scan_name(&mut self, terminator: char) -> Result<String, Error> {
+        let start = self.position;
+        loop {
+            match self.next_char() {
+                Some(c) if c == terminator => break,
+                Some(_) => {}
+                None => return Err(Error::syntax("Group name is not closed")),
+            }
+        }
+        let name = &self.input[start..self.position - terminator.len_utf8()];
+
+        if !is_valid_group_name(name) {
+            return Err(Error::invalid_group_name(name));
+        }
+
+        Ok(name.to_string())
+    }
+}
+
+// エスケープしないと特別な意味を持ちうる文字
+pub fn is_meta_character(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.'
+            | '+'
+            | '*'
+            | '?'
+            | '('
+            | ')'
+            | '|'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '^'
+            | '$'
+            | '#'
+            | '&'
+            | '-'
+            | '~'
+    )
+}
+
+// 英数字以外のASCII文字はエスケープすると常にその文字自身を表す
+fn is_escapable_character(c: char) -> bool {
+    c.is_ascii() && !c.is_ascii_alphanumeric()
+}
+
+// グループ名は英字または '_' で始まり、英数字と '_' のみからなる
+fn is_valid_group_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ErrorKind;
+
+    // 最初に発生したエラーを返す
+    fn first_error(pattern: &str) -> Error {
+        let mut lexer = Lexer::new(pattern);
+        loop {
+            match lexer.next_token() {
+                Ok(Token::EndOfFile) => panic!("{}", pattern),
+                Ok(_) => {}
+                Err(error) => return error,
+            }
+        }
+    }
 
     #[test]
     fn test_lexer() {
         let mut lexer = Lexer::new("a|b");
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::AlternateOperator);
-        assert_eq!(lexer.next_token(), Token::Character('b'));
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::AlternateOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
 
         lexer = Lexer::new("a*");
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::StarOperator);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::StarOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
 
         lexer = Lexer::new("a+");
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::PlusOperator);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::PlusOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
 
         lexer = Lexer::new("a?");
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::QuestionOperator);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::QuestionOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        lexer = Lexer::new(r"a.\.");
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::Dot));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('.')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
 
         lexer = Lexer::new("(a)");
-        assert_eq!(lexer.next_token(), Token::OpenParenthesis);
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::CloseParenthesis);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.next_token(), Ok(Token::OpenParenthesis));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseParenthesis));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        lexer = Lexer::new("^a$");
+        assert_eq!(lexer.next_token(), Ok(Token::StartAnchor));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndAnchor));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+    }
+
+    #[test]
+    fn test_named_group() {
+        for pattern in ["(?P<year>a)", "(?<year>a)", "(?'year'a)"] {
+            let mut lexer = Lexer::new(pattern);
+            assert_eq!(
+                lexer.next_token(),
+                Ok(Token::OpenNamedGroup("year".to_string()))
+            );
+            assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+            assert_eq!(lexer.next_token(), Ok(Token::CloseParenthesis));
+            assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+        }
+
+        for pattern in ["(?P<>a)", "(?<1a>a)", "(?<a-b>a)", "(?'a>a)"] {
+            let mut lexer = Lexer::new(pattern);
+            assert!(lexer.next_token().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_lookaround() {
+        let cases = [
+            ("(?=", LookaroundKind::PositiveLookahead),
+            ("(?!", LookaroundKind::NegativeLookahead),
+            ("(?<=", LookaroundKind::PositiveLookbehind),
+            ("(?<!", LookaroundKind::NegativeLookbehind),
+        ];
+        for (pattern, kind) in cases {
+            let mut lexer = Lexer::new(pattern);
+            assert_eq!(lexer.next_token(), Ok(Token::OpenLookaround(kind)));
+            assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+        }
+    }
+
+    #[test]
+    fn test_span() {
+        let mut lexer = Lexer::new("a\n\\d{2}");
+        let expected = [
+            (Token::Character('a'), (0, 1, 1), (1, 1, 2)),
+            (Token::Character('\n'), (1, 1, 2), (2, 2, 1)),
+            (
+                Token::PerlClass(PerlClass {
+                    kind: PerlClassKind::Digit,
+                    negated: false,
+                }),
+                (2, 2, 1),
+                (4, 2, 3),
+            ),
+            (Token::RepetitionOperator(2, Some(2)), (4, 2, 3), (7, 2, 6)),
+            (Token::EndOfFile, (7, 2, 6), (7, 2, 6)),
+        ];
+        for (token, start, end) in expected {
+            let span = Span::new(
+                Position::new(start.0, start.1, start.2),
+                Position::new(end.0, end.1, end.2),
+            );
+            assert_eq!(lexer.next_token_with_span(), Ok(Spanned::new(token, span)));
+        }
+    }
+
+    #[test]
+    fn test_quote() {
+        let mut lexer = Lexer::new(r"\Qa.\*\E+\Q(");
+        for c in ['a', '.', '\\', '*'] {
+            assert_eq!(lexer.next_token(), Ok(Token::Character(c)));
+        }
+        assert_eq!(lexer.next_token(), Ok(Token::PlusOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('(')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        // x フラグが有効でも空白を読み飛ばさない
+        let mut lexer = Lexer::new(r"\Q a\E b");
+        lexer.set_ignore_whitespace(true);
+        assert_eq!(lexer.next_token(), Ok(Token::Character(' ')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+    }
+
+    #[test]
+    fn test_atomic_group() {
+        let mut lexer = Lexer::new("(?>a)");
+        assert_eq!(lexer.next_token(), Ok(Token::OpenAtomicGroup));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseParenthesis));
+    }
+
+    #[test]
+    fn test_backreference() {
+        let mut lexer = Lexer::new(r"\1\12\k<name>[\k]");
+        assert_eq!(lexer.next_token(), Ok(Token::Backreference(1)));
+        assert_eq!(lexer.next_token(), Ok(Token::Backreference(12)));
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::NamedBackreference("name".to_string()))
+        );
+        assert_eq!(lexer.next_token(), Ok(Token::OpenBracket));
+        lexer.set_in_class(true);
+        assert!(lexer.next_token().is_err());
+
+        for pattern in [r"\k", r"\kname", r"\k<1>", r"\k<name"] {
+            let mut lexer = Lexer::new(pattern);
+            assert!(lexer.next_token().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut lexer = Lexer::new("(?:a)");
+        assert_eq!(lexer.next_token(), Ok(Token::OpenGroup(Flags::default())));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseParenthesis));
+
+        lexer = Lexer::new("(?i-sm)(?s:");
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::SetFlags(Flags {
+                case_insensitive: Some(true),
+                multi_line: Some(false),
+                dot_matches_new_line: Some(false),
+                ignore_whitespace: None,
+                unicode: None,
+                crlf: None,
+            }))
+        );
+        assert_eq!(
+            Lexer::new("(?mR)").next_token(),
+            Ok(Token::SetFlags(Flags {
+                multi_line: Some(true),
+                crlf: Some(true),
+                ..Flags::default()
+            }))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::OpenGroup(Flags {
+                dot_matches_new_line: Some(true),
+                ..Flags::default()
+            }))
+        );
+
+        for pattern in ["(?)", "(?i", "(?q)", "(?ii)", "(?i-)", "(?-:", "(?i-s-m)"] {
+            let mut lexer = Lexer::new(pattern);
+            assert!(lexer.next_token().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_ignore_whitespace() {
+        let mut lexer = Lexer::new(" a # comment\n\tb c");
+        lexer.set_ignore_whitespace(true);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+        lexer.set_ignore_whitespace(false);
+        assert_eq!(lexer.next_token(), Ok(Token::Character(' ')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('c')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        let mut lexer = Lexer::new("{ 2 , 3 }{2 # comment\n}{ a}");
+        lexer.set_ignore_whitespace(true);
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::RepetitionOperator(2, Some(3)))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::RepetitionOperator(2, Some(2)))
+        );
+        assert_eq!(lexer.next_token(), Ok(Token::Character('{')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut lexer = Lexer::new(r"\*\+\?\(\)\|\^\$\\\ ");
+        for c in ['*', '+', '?', '(', ')', '|', '^', '$', '\\', ' '] {
+            assert_eq!(lexer.next_token(), Ok(Token::Character(c)));
+        }
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        let mut lexer = Lexer::new(r"\n\t\r\f\v\a\e\x41\x{1F600}\u{3042}\u00e9\0\012\o{101}");
+        for c in [
+            '\n', '\t', '\r', '\x0C', '\x0B', '\x07', '\x1B', 'A', '😀', 'あ', 'é', '\0', '\n', 'A',
+        ] {
+            assert_eq!(lexer.next_token(), Ok(Token::Character(c)));
+        }
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let mut lexer = Lexer::new(r"\b\B\<\>\b{start}\b{end}\b{2}[\<\>]");
+        let assertion = |assertion| Ok(Token::Assertion(assertion));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordBoundary));
+        assert_eq!(lexer.next_token(), assertion(Assertion::NotWordBoundary));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordStart));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordEnd));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordStart));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordEnd));
+        assert_eq!(lexer.next_token(), assertion(Assertion::WordBoundary));
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::RepetitionOperator(2, Some(2)))
+        );
+        assert_eq!(lexer.next_token(), Ok(Token::OpenBracket));
+        lexer.set_in_class(true);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('<')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('>')));
+
+        let mut lexer = Lexer::new(r"\A\z\Z");
+        assert_eq!(lexer.next_token(), assertion(Assertion::StartText));
+        assert_eq!(lexer.next_token(), assertion(Assertion::EndText));
+        assert_eq!(
+            lexer.next_token(),
+            assertion(Assertion::EndTextOptionalNewline)
+        );
+
+        let mut lexer = Lexer::new(r"a\b{foo}");
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap_err().position(), Some(1));
+    }
+
+    #[test]
+    fn test_perl_class() {
+        let mut lexer = Lexer::new(r"\d\W[\s]");
+        let class = |kind, negated| Ok(Token::PerlClass(PerlClass { kind, negated }));
+        assert_eq!(lexer.next_token(), class(PerlClassKind::Digit, false));
+        assert_eq!(lexer.next_token(), class(PerlClassKind::Word, true));
+        assert_eq!(lexer.next_token(), Ok(Token::OpenBracket));
+        lexer.set_in_class(true);
+        assert_eq!(lexer.next_token(), class(PerlClassKind::Space, false));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseBracket));
+    }
+
+    #[test]
+    fn test_unicode_class() {
+        let mut lexer = Lexer::new(r"\pL\P{Greek}\p{sc=Latn}");
+        let class = |name: &str, value: Option<&str>, negated| {
+            Ok(Token::UnicodeClass(UnicodeClass {
+                name: name.to_string(),
+                value: value.map(str::to_string),
+                negated,
+            }))
+        };
+        assert_eq!(lexer.next_token(), class("L", None, false));
+        assert_eq!(lexer.next_token(), class("Greek", None, true));
+        assert_eq!(lexer.next_token(), class("sc", Some("Latn"), false));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        let error = first_error(r"a\p{Foo}");
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::UnknownPropertyName("Foo".to_string())
+        );
+        assert_eq!(error.position(), Some(1));
+
+        let error = first_error(r"\p{gc=Foo}");
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::UnknownPropertyValue("Foo".to_string())
+        );
+
+        for pattern in [r"\p", r"\p{L", r"\p{}", r"\p{foo=L}"] {
+            assert!(Lexer::new(pattern).next_token().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_escape_error() {
+        for (pattern, position) in [
+            (r"\", 0),
+            (r"ab\q", 2),
+            (r"a\x4", 1),
+            (r"\x{41", 0),
+            (r"\x{}", 0),
+            (r"\x{110000}", 0),
+            (r"\u{D800}", 0),
+            (r"\uzzzz", 0),
+            (r"\o{8}", 0),
+            (r"\é", 0),
+        ] {
+            let error = first_error(pattern);
+            assert!(matches!(error.kind(), ErrorKind::SyntaxError(_)));
+            assert_eq!(error.position(), Some(position), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_class() {
+        let mut lexer = Lexer::new(r"[^a-]\]");
+        assert_eq!(lexer.next_token(), Ok(Token::OpenNegatedBracket));
+        lexer.set_in_class(true);
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::RangeOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseBracket));
+        assert_eq!(lexer.next_token(), Ok(Token::Character(']')));
+        lexer.set_in_class(false);
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        // 文字クラスの中では量指定子や括弧も文字として扱う
+        let mut lexer = Lexer::new("[*(|");
+        assert_eq!(lexer.next_token(), Ok(Token::OpenBracket));
+        lexer.set_in_class(true);
+        for c in ['*', '(', '|'] {
+            assert_eq!(lexer.next_token(), Ok(Token::Character(c)));
+        }
+    }
+
+    #[test]
+    fn test_class_set() {
+        let mut lexer = Lexer::new("a&&[^b]--c~~&-~");
+        lexer.set_in_class(true);
+        let op = |kind| Ok(Token::ClassSetOperator(kind));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), op(ClassSetOpKind::Intersection));
+        assert_eq!(lexer.next_token(), Ok(Token::OpenNegatedBracket));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+        assert_eq!(lexer.next_token(), Ok(Token::CloseBracket));
+        assert_eq!(lexer.next_token(), op(ClassSetOpKind::Difference));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('c')));
+        assert_eq!(lexer.next_token(), op(ClassSetOpKind::SymmetricDifference));
+        // 1文字だけの場合は演算子にならない
+        assert_eq!(lexer.next_token(), Ok(Token::Character('&')));
+        assert_eq!(lexer.next_token(), Ok(Token::RangeOperator));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('~')));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut lexer = Lexer::new("a{2}b{3,}c{0,10}");
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::RepetitionOperator(2, Some(2)))
+        );
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+        assert_eq!(lexer.next_token(), Ok(Token::RepetitionOperator(3, None)));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('c')));
+        assert_eq!(
+            lexer.next_token(),
+            Ok(Token::RepetitionOperator(0, Some(10)))
+        );
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        // 形式に合わない { は文字として扱う
+        for pattern in ["{", "{}", "{,2}", "{1", "{1,", "{1,2", "{a}", "{1 }"] {
+            let mut lexer = Lexer::new(pattern);
+            assert_eq!(lexer.next_token(), Ok(Token::Character('{')), "{}", pattern);
+        }
+
+        for (pattern, position) in [("a{2,1}", 1), ("a{99999999999}", 1)] {
+            let mut lexer = Lexer::new(pattern);
+            lexer.next_token().unwrap();
+            let error = lexer.next_token().unwrap_err();
+            assert_eq!(error.position(), Some(position), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_posix_class() {
+        let mut lexer = Lexer::new("[:alpha:][:^digit:][:x][::]");
+        lexer.set_in_class(true);
+        let class = |kind, negated| Ok(Token::PosixClass(PosixClass { kind, negated }));
+        assert_eq!(lexer.next_token(), class(PosixClassKind::Alpha, false));
+        assert_eq!(lexer.next_token(), class(PosixClassKind::Digit, true));
+        // 形式に合わない場合は入れ子の文字クラスとして扱う
+        for c in "[:x][::]".chars() {
+            let token = match c {
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                _ => Token::Character(c),
+            };
+            assert_eq!(lexer.next_token(), Ok(token));
+        }
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+
+        let mut lexer = Lexer::new("a[:foo:]");
+        lexer.set_in_class(true);
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap_err().position(), Some(1));
+
+        // 文字クラスの外では通常の括弧として扱う
+        let mut lexer = Lexer::new("[:alpha:]");
+        assert_eq!(lexer.next_token(), Ok(Token::OpenBracket));
     }
 
     #[test]
     fn test_peek() {
         let mut lexer = Lexer::new("abcde");
-        assert_eq!(lexer.peek_token(), Token::Character('a'));
-        assert_eq!(lexer.peek_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::Character('a'));
-        assert_eq!(lexer.next_token(), Token::Character('b'));
-        assert_eq!(lexer.peek_token(), Token::Character('c'));
-        lexer.next_token();
-        lexer.next_token();
-        assert_eq!(lexer.peek_token(), Token::Character('e'));
-        assert_eq!(lexer.next_token(), Token::Character('e'));
-        assert_eq!(lexer.peek_token(), Token::EndOfFile);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
-        assert_eq!(lexer.peek_token(), Token::EndOfFile);
-        assert_eq!(lexer.next_token(), Token::EndOfFile);
+        assert_eq!(lexer.peek_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.peek_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('a')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('b')));
+        assert_eq!(lexer.peek_token(), Ok(Token::Character('c')));
+        lexer.next_token().unwrap();
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.peek_token(), Ok(Token::Character('e')));
+        assert_eq!(lexer.next_token(), Ok(Token::Character('e')));
+        assert_eq!(lexer.peek_token(), Ok(Token::EndOfFile));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
+        assert_eq!(lexer.peek_token(), Ok(Token::EndOfFile));
+        assert_eq!(lexer.next_token(), Ok(Token::EndOfFile));
     }
 }
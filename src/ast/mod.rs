@@ -1,12 +1,220 @@
 mod lexer;
-pub mod parser;
+mod parser;
+
+pub(crate) use lexer::is_meta_character;
+pub use lexer::Token;
+pub use parser::Parser;
+
+// パターン中の位置。offset はバイト単位、line と column は1から数え、column は文字単位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub const fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+// パターン中の範囲。end は範囲の直後の位置を指す
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub const fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    // 長さ0の範囲
+    pub const fn splat(position: Position) -> Self {
+        Self::new(position, position)
+    }
+}
+
+// 範囲を付けた値。トークンや文字クラスの要素など、Ast 以外の構文の位置を表す
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub const fn new(value: T, span: Span) -> Self {
+        Self { value, span }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Ast {
+    pub kind: AstKind,
+    pub span: Span,
+}
+
+impl Ast {
+    pub fn new(kind: AstKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AstKind {
+    Empty,                      // "", a|, ()
+    Literal(char),              // a
+    Dot,                        // .
+    Concat(Vec<Ast>),           // ab
+    Alternate(Vec<Ast>),        // a|b
+    Repetition(Repetition),     // +, *, ?, {n,m}, +?
+    Assertion(Assertion),       // ^, $, \b, \A
+    Capture(Capture),           // (a)
+    Group(Group),               // (?:a), (?i:a)
+    Lookaround(Lookaround),     // (?=a), (?<!a)
+    AtomicGroup(Box<Ast>),      // (?>a), a*+
+    Backreference(u32),         // \1, \k<name>
+    Flags(Flags),               // (?i)
+    Class(Class),               // [a-z], [^a]
+    PerlClass(PerlClass),       // \d, \W
+    UnicodeClass(UnicodeClass), // \pL, \p{Greek}, \P{sc=Grek}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Class {
+    pub negated: bool,
+    pub set: Spanned<ClassSet>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClassSet {
+    Union(Vec<Spanned<ClassItem>>), // a-z\d
+    BinaryOp(ClassSetBinaryOp),     // a-z--aeiou
+}
+
+// 演算子の優先順位はすべて同じで、左結合とする
+#[derive(Debug, PartialEq)]
+pub struct ClassSetBinaryOp {
+    pub kind: ClassSetOpKind,
+    pub lhs: Box<Spanned<ClassSet>>,
+    pub rhs: Box<Spanned<ClassSet>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClassSetOpKind {
+    Intersection,        // &&
+    Difference,          // --
+    SymmetricDifference, // ~~
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClassItem {
+    Literal(char),         // a
+    Range(char, char),     // a-z
+    Perl(PerlClass),       // \d
+    Unicode(UnicodeClass), // \p{Greek}
+    Posix(PosixClass),     // [:alpha:]
+    Bracketed(Box<Class>), // [aeiou]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PosixClass {
+    pub kind: PosixClassKind,
+    pub negated: bool, // [:^alpha:]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PosixClassKind {
+    Alnum,
+    Alpha,
+    Ascii,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Word,
+    Xdigit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PerlClass {
+    pub kind: PerlClassKind,
+    pub negated: bool, // \D, \W, \S
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnicodeClass {
+    pub name: String,
+    pub value: Option<String>, // \p{name=value}
+    pub negated: bool,         // \P
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PerlClassKind {
+    Digit, // \d
+    Word,  // \w
+    Space, // \s
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Capture {
+    pub index: u32,
+    pub name: Option<String>,
+    pub ast: Box<Ast>,
+}
 
 #[derive(Debug, PartialEq)]
-pub enum Ast {
-    Literal(char),          // a
-    Concat(Vec<Ast>),       // ab
-    Alternate(Vec<Ast>),    // a|b
-    Repetition(Repetition), // +, *
+pub struct Lookaround {
+    pub kind: LookaroundKind,
+    pub ast: Box<Ast>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LookaroundKind {
+    PositiveLookahead,  // (?=
+    NegativeLookahead,  // (?!
+    PositiveLookbehind, // (?<=
+    NegativeLookbehind, // (?<!
+}
+
+impl LookaroundKind {
+    pub fn is_negated(&self) -> bool {
+        matches!(
+            self,
+            LookaroundKind::NegativeLookahead | LookaroundKind::NegativeLookbehind
+        )
+    }
+
+    pub fn is_behind(&self) -> bool {
+        matches!(
+            self,
+            LookaroundKind::PositiveLookbehind | LookaroundKind::NegativeLookbehind
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Assertion {
+    StartLine,              // ^
+    EndLine,                // $
+    StartText,              // \A
+    EndText,                // \z
+    EndTextOptionalNewline, // \Z
+    WordBoundary,           // \b
+    NotWordBoundary,        // \B
+    WordStart,              // \<, \b{start}
+    WordEnd,                // \>, \b{end}
 }
 
 #[derive(Debug, PartialEq)]
@@ -14,4 +222,62 @@ pub struct Repetition {
     pub ast: Box<Ast>,
     pub min: u32,
     pub max: Option<u32>,
+    pub greedy: bool, // *? などの最短一致では false
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    pub flags: Flags,
+    pub ast: Box<Ast>,
+}
+
+// 各フラグの有効(Some(true))・無効(Some(false))・指定なし(None)を表す
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Flags {
+    pub case_insensitive: Option<bool>,     // i
+    pub multi_line: Option<bool>,           // m
+    pub dot_matches_new_line: Option<bool>, // s
+    pub ignore_whitespace: Option<bool>,    // x
+    pub unicode: Option<bool>,              // u
+    pub crlf: Option<bool>,                 // R
+}
+
+impl Flags {
+    // other で指定されたフラグで上書きする
+    pub fn merge(&mut self, other: &Flags) {
+        fn merge_flag(flag: &mut Option<bool>, other: Option<bool>) {
+            if other.is_some() {
+                *flag = other;
+            }
+        }
+
+        merge_flag(&mut self.case_insensitive, other.case_insensitive);
+        merge_flag(&mut self.multi_line, other.multi_line);
+        merge_flag(&mut self.dot_matches_new_line, other.dot_matches_new_line);
+        merge_flag(&mut self.ignore_whitespace, other.ignore_whitespace);
+        merge_flag(&mut self.unicode, other.unicode);
+        merge_flag(&mut self.crlf, other.crlf);
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive.unwrap_or(false)
+    }
+
+    pub fn is_multi_line(&self) -> bool {
+        self.multi_line.unwrap_or(false)
+    }
+
+    // 有効な場合、複数行モードの ^ と $ は \r\n も改行として扱う
+    pub fn is_crlf(&self) -> bool {
+        self.crlf.unwrap_or(false)
+    }
+
+    pub fn is_dot_matches_new_line(&self) -> bool {
+        self.dot_matches_new_line.unwrap_or(false)
+    }
+
+    // 無効な場合、\d などは ASCII の文字のみを表す
+    pub fn is_unicode(&self) -> bool {
+        self.unicode.unwrap_or(true)
+    }
 }
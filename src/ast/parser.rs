@@ -1,112 +1,442 @@
 use super::lexer::{Lexer, Token};
-use super::{Ast, Repetition};
+use super::{
+    Assertion, Ast, AstKind, Capture, Class, ClassItem, ClassSet, ClassSetBinaryOp, Flags, Group,
+    Lookaround, Position, Repetition, Span, Spanned,
+};
 use crate::error::Error;
 
 #[derive(Debug)]
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
+    capture_names: Vec<Option<String>>,
+    // 読んだトークンとその範囲
+    tokens: Vec<Spanned<Token>>,
+    // 最後に読んだトークンの範囲
+    last_span: Span,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(pattern: &'a str) -> Self {
         Self {
             lexer: Lexer::new(pattern),
+            // グループ0はマッチ全体を表し、名前を持たない
+            capture_names: vec![None],
+            tokens: Vec::new(),
+            last_span: Span::splat(Position::new(0, 1, 1)),
         }
     }
 
+    // パターン全体に適用するフラグのうち、字句解析に関わるものを反映して構築する
+    pub fn with_flags(pattern: &'a str, flags: &Flags) -> Self {
+        let mut parser = Self::new(pattern);
+        parser.set_flags(flags);
+        parser
+    }
+
     pub fn parse(&mut self) -> Result<Ast, Error> {
-        self.parse_alternate()
+        let ast = self.parse_alternate()?;
+
+        match self.current_token()? {
+            Token::EndOfFile => Ok(ast),
+            Token::CloseParenthesis => Err(self.error_at_current("Open parenthesis is missing")),
+            _ => Err(self.error_at_current("Unexpected token")),
+        }
     }
 
-    fn next(&mut self) {
-        self.lexer.next_token();
+    // 各グループの名前をグループ番号順に返す
+    pub fn capture_names(&self) -> &[Option<String>] {
+        &self.capture_names
     }
 
-    fn current_token(&mut self) -> Token {
+    // 構文解析で読んだトークンを範囲とともに読んだ順に返す。文字クラスの中かどうかや
+    // x フラグの有無は構文解析の結果に従って区別される。エラーの場合はそこまでに読んだトークンを返す
+    pub fn tokens(&self) -> &[Spanned<Token>] {
+        &self.tokens
+    }
+
+    fn next(&mut self) -> Result<(), Error> {
+        let token = self.lexer.next_token_with_span()?;
+        self.last_span = token.span;
+        self.tokens.push(token);
+        Ok(())
+    }
+
+    fn current_token(&mut self) -> Result<Token, Error> {
         self.lexer.peek_token()
     }
 
+    // 次のトークンの開始位置
+    fn current_position(&self) -> Result<Position, Error> {
+        let token = self.lexer.clone().next_token_with_span()?;
+        Ok(token.span.start)
+    }
+
+    // 次のトークンの位置を指すエラーを作る
+    fn error_at_current(&self, message: &str) -> Error {
+        match self.current_position() {
+            Ok(position) => Error::parse(message).at(position.offset),
+            Err(error) => error,
+        }
+    }
+
+    // 次のトークンから読んだ最後のトークンまでを範囲とするノードを作る
     fn parse_literal(&mut self) -> Result<Ast, Error> {
-        match self.current_token() {
+        let start = self.current_position()?;
+        let kind = self.parse_literal_kind()?;
+        Ok(Ast::new(kind, Span::new(start, self.last_span.end)))
+    }
+
+    fn parse_literal_kind(&mut self) -> Result<AstKind, Error> {
+        match self.current_token()? {
             Token::Character(c) => {
-                self.next();
-                Ok(Ast::Literal(c))
+                self.next()?;
+                Ok(AstKind::Literal(c))
+            }
+            Token::Dot => {
+                self.next()?;
+                Ok(AstKind::Dot)
+            }
+            Token::PerlClass(class) => {
+                self.next()?;
+                Ok(AstKind::PerlClass(class))
+            }
+            Token::UnicodeClass(class) => {
+                self.next()?;
+                Ok(AstKind::UnicodeClass(class))
+            }
+            Token::StartAnchor => {
+                self.next()?;
+                Ok(AstKind::Assertion(Assertion::StartLine))
+            }
+            Token::EndAnchor => {
+                self.next()?;
+                Ok(AstKind::Assertion(Assertion::EndLine))
+            }
+            Token::Assertion(assertion) => {
+                self.next()?;
+                Ok(AstKind::Assertion(assertion))
             }
             Token::OpenParenthesis => {
-                self.next();
-                let ast = self.parse_alternate()?;
-                if let Token::CloseParenthesis = self.current_token() {
-                    self.next();
-                    Ok(ast)
-                } else {
-                    Err(Error::parse("Close parenthesis is missing"))
+                self.next()?;
+                self.parse_capture(None)
+            }
+            Token::OpenNamedGroup(name) => {
+                self.next()?;
+                if self.capture_names.iter().flatten().any(|n| *n == name) {
+                    return Err(Error::duplicate_group_name(&name).at(self.last_span.start.offset));
+                }
+                self.parse_capture(Some(name))
+            }
+            Token::OpenGroup(flags) => {
+                self.next()?;
+                let ast = self.parse_group_body(&flags)?;
+                Ok(AstKind::Group(Group {
+                    flags,
+                    ast: Box::new(ast),
+                }))
+            }
+            Token::OpenLookaround(kind) => {
+                self.next()?;
+                let ast = self.parse_group_body(&Flags::default())?;
+                Ok(AstKind::Lookaround(Lookaround {
+                    kind,
+                    ast: Box::new(ast),
+                }))
+            }
+            Token::OpenAtomicGroup => {
+                self.next()?;
+                let ast = self.parse_group_body(&Flags::default())?;
+                Ok(AstKind::AtomicGroup(Box::new(ast)))
+            }
+            Token::Backreference(index) => {
+                self.next()?;
+                if index as usize >= self.capture_names.len() {
+                    return Err(Error::parse("Backreference to an undefined group")
+                        .at(self.last_span.start.offset));
+                }
+                Ok(AstKind::Backreference(index))
+            }
+            Token::NamedBackreference(name) => {
+                self.next()?;
+                let index = self
+                    .capture_names
+                    .iter()
+                    .position(|n| n.as_deref() == Some(name.as_str()))
+                    .ok_or(
+                        Error::parse("Backreference to an undefined group")
+                            .at(self.last_span.start.offset),
+                    )?;
+                Ok(AstKind::Backreference(index as u32))
+            }
+            Token::SetFlags(flags) => {
+                self.next()?;
+                // (?x) などはグループの終わりまで有効
+                self.set_flags(&flags);
+                Ok(AstKind::Flags(flags))
+            }
+            Token::OpenBracket => {
+                self.next()?;
+                self.parse_bracket(false)
+            }
+            Token::OpenNegatedBracket => {
+                self.next()?;
+                self.parse_bracket(true)
+            }
+            _ => Err(self.error_at_current("Unexpected token")),
+        }
+    }
+
+    fn parse_bracket(&mut self, negated: bool) -> Result<AstKind, Error> {
+        self.lexer.set_in_class(true);
+        let class = self.parse_class(negated)?;
+        self.lexer.set_in_class(false);
+
+        Ok(AstKind::Class(class))
+    }
+
+    // [ の直後から閉じ括弧までを読む。集合演算は左結合で、和集合より優先順位が低い
+    fn parse_class(&mut self, negated: bool) -> Result<Class, Error> {
+        let open = self.last_span.start.offset;
+        let mut set = self.parse_class_union(true)?;
+
+        loop {
+            match self.current_token()? {
+                Token::CloseBracket => break,
+                Token::ClassSetOperator(kind) => {
+                    self.next()?;
+                    let rhs = self.parse_class_union(false)?;
+                    let span = Span::new(set.span.start, rhs.span.end);
+                    let op = ClassSetBinaryOp {
+                        kind,
+                        lhs: Box::new(set),
+                        rhs: Box::new(rhs),
+                    };
+                    set = Spanned::new(ClassSet::BinaryOp(op), span);
                 }
+                Token::EndOfFile => return Err(Error::parse("Close bracket is missing").at(open)),
+                _ => return Err(self.error_at_current("Unexpected token in class")),
             }
-            _ => Err(Error::parse("Unexpected token")),
         }
+        self.next()?;
+
+        Ok(Class { negated, set })
     }
 
+    // 閉じ括弧か集合演算子の手前までを読む。要素がない場合は長さ0の範囲になる
+    fn parse_class_union(&mut self, first: bool) -> Result<Spanned<ClassSet>, Error> {
+        let start = self.current_position()?;
+        let mut items = Vec::new();
+        // 先頭の ] は文字として扱う
+        if first && self.current_token()? == Token::CloseBracket {
+            self.next()?;
+            items.push(Spanned::new(ClassItem::Literal(']'), self.last_span));
+        }
+
+        loop {
+            match self.current_token()? {
+                Token::CloseBracket | Token::ClassSetOperator(_) | Token::EndOfFile => break,
+                _ => {
+                    let start = self.current_position()?;
+                    let item = self.parse_class_item()?;
+                    items.push(Spanned::new(item, Span::new(start, self.last_span.end)));
+                }
+            }
+        }
+
+        let span = match items.last() {
+            Some(last) => Span::new(start, last.span.end),
+            None => Span::splat(start),
+        };
+        Ok(Spanned::new(ClassSet::Union(items), span))
+    }
+
+    fn parse_class_item(&mut self) -> Result<ClassItem, Error> {
+        match self.current_token()? {
+            Token::PerlClass(class) => {
+                self.next()?;
+                return Ok(ClassItem::Perl(class));
+            }
+            Token::UnicodeClass(class) => {
+                self.next()?;
+                return Ok(ClassItem::Unicode(class));
+            }
+            Token::PosixClass(class) => {
+                self.next()?;
+                return Ok(ClassItem::Posix(class));
+            }
+            Token::OpenBracket | Token::OpenNegatedBracket => {
+                let negated = self.current_token()? == Token::OpenNegatedBracket;
+                self.next()?;
+                return Ok(ClassItem::Bracketed(Box::new(self.parse_class(negated)?)));
+            }
+            _ => {}
+        }
+
+        let position = self.current_position()?.offset;
+        let start = self.parse_class_char()?;
+        if self.current_token()? != Token::RangeOperator {
+            return Ok(ClassItem::Literal(start));
+        }
+
+        // 閉じ括弧の直前の - は文字として扱う
+        let mut lexer = self.lexer.clone();
+        lexer.next_token()?;
+        if lexer.next_token()? == Token::CloseBracket {
+            return Ok(ClassItem::Literal(start));
+        }
+
+        self.next()?;
+        let end = self.parse_class_char()?;
+        if start > end {
+            return Err(Error::parse("Invalid class range").at(position));
+        }
+
+        Ok(ClassItem::Range(start, end))
+    }
+
+    fn parse_class_char(&mut self) -> Result<char, Error> {
+        match self.current_token()? {
+            Token::Character(c) => {
+                self.next()?;
+                Ok(c)
+            }
+            Token::RangeOperator => {
+                self.next()?;
+                Ok('-')
+            }
+            _ => Err(self.error_at_current("Unexpected token in class")),
+        }
+    }
+
+    fn set_flags(&mut self, flags: &Flags) {
+        if let Some(ignore_whitespace) = flags.ignore_whitespace {
+            self.lexer.set_ignore_whitespace(ignore_whitespace);
+        }
+    }
+
+    // 開き括弧の直後から閉じ括弧までを読む。グループ内で変更したフラグは閉じ括弧で元に戻す
+    fn parse_group_body(&mut self, flags: &Flags) -> Result<Ast, Error> {
+        let open = self.last_span.start.offset;
+        let ignore_whitespace = self.lexer.ignore_whitespace();
+        self.set_flags(flags);
+
+        let ast = self.parse_alternate()?;
+        if let Token::CloseParenthesis = self.current_token()? {
+            self.next()?;
+            self.lexer.set_ignore_whitespace(ignore_whitespace);
+            Ok(ast)
+        } else {
+            Err(Error::parse("Close parenthesis is missing").at(open))
+        }
+    }
+
+    fn parse_capture(&mut self, name: Option<String>) -> Result<AstKind, Error> {
+        // グループ番号は開き括弧の出現順に割り当てる
+        let index = self.capture_names.len() as u32;
+        self.capture_names.push(name.clone());
+
+        let ast = self.parse_group_body(&Flags::default())?;
+        Ok(AstKind::Capture(Capture {
+            index,
+            name,
+            ast: Box::new(ast),
+        }))
+    }
+
+    // 空の選択肢は空文字列にマッチする
     fn parse_concat(&mut self) -> Result<Ast, Error> {
-        let mut nodes = vec![self.parse_repetition()?];
+        let mut nodes = Vec::new();
 
         while matches!(
-            self.current_token(),
-            Token::Character(_) | Token::OpenParenthesis
+            self.current_token()?,
+            Token::Character(_)
+                | Token::Dot
+                | Token::PerlClass(_)
+                | Token::UnicodeClass(_)
+                | Token::OpenParenthesis
+                | Token::OpenNamedGroup(_)
+                | Token::OpenGroup(_)
+                | Token::OpenLookaround(_)
+                | Token::OpenAtomicGroup
+                | Token::Backreference(_)
+                | Token::NamedBackreference(_)
+                | Token::SetFlags(_)
+                | Token::OpenBracket
+                | Token::OpenNegatedBracket
+                | Token::StartAnchor
+                | Token::EndAnchor
+                | Token::Assertion(_)
         ) {
             nodes.push(self.parse_repetition()?);
         }
 
         match nodes.len() {
-            0 => Err(Error::parse("Expected at least one node in concat")),
+            0 => Ok(Ast::new(AstKind::Empty, Span::splat(self.last_span.end))),
             1 => Ok(nodes.pop().unwrap()),
-            _ => Ok(Ast::Concat(nodes)),
+            _ => {
+                let span = Span::new(nodes[0].span.start, nodes[nodes.len() - 1].span.end);
+                Ok(Ast::new(AstKind::Concat(nodes), span))
+            }
         }
     }
 
     fn parse_alternate(&mut self) -> Result<Ast, Error> {
         let mut nodes = vec![self.parse_concat()?];
 
-        while matches!(self.current_token(), Token::AlternateOperator) {
-            self.next();
+        while matches!(self.current_token()?, Token::AlternateOperator) {
+            self.next()?;
             nodes.push(self.parse_concat()?);
         }
 
         match nodes.len() {
             0 => Err(Error::parse("Expected at least one node in alternate")),
             1 => Ok(nodes.pop().unwrap()),
-            _ => Ok(Ast::Alternate(nodes)),
+            _ => {
+                let span = Span::new(nodes[0].span.start, nodes[nodes.len() - 1].span.end);
+                Ok(Ast::new(AstKind::Alternate(nodes), span))
+            }
         }
     }
 
     fn parse_repetition(&mut self) -> Result<Ast, Error> {
-        let ast = self.parse_literal()?;
+        let mut ast = self.parse_literal()?;
+        if let AstKind::Flags(_) = ast.kind {
+            return Ok(ast);
+        }
 
-        match self.current_token() {
-            Token::StarOperator => {
-                self.next();
-                Ok(Ast::Repetition(Repetition {
-                    ast: Box::new(ast),
-                    min: 0,
-                    max: None,
-                }))
-            }
-            Token::PlusOperator => {
-                self.next();
-                Ok(Ast::Repetition(Repetition {
-                    ast: Box::new(ast),
-                    min: 1,
-                    max: None,
-                }))
+        // a{2}{3} のように量指定子は重ねて指定できる
+        loop {
+            let (min, max) = match self.current_token()? {
+                Token::StarOperator => (0, None),
+                Token::PlusOperator => (1, None),
+                Token::QuestionOperator => (0, Some(1)),
+                Token::RepetitionOperator(min, max) => (min, max),
+                _ => return Ok(ast),
+            };
+            self.next()?;
+
+            // 直後の ? は最短一致を、+ は繰り返した部分へ戻らないことを表す
+            let next = self.current_token()?;
+            let greedy = next != Token::QuestionOperator;
+            let possessive = next == Token::PlusOperator;
+            if !greedy || possessive {
+                self.next()?;
             }
-            Token::QuestionOperator => {
-                self.next();
-                Ok(Ast::Repetition(Repetition {
+
+            // 範囲は繰り返す部分の先頭から量指定子の終わりまで
+            let span = Span::new(ast.span.start, self.last_span.end);
+            ast = Ast::new(
+                AstKind::Repetition(Repetition {
                     ast: Box::new(ast),
-                    min: 0,
-                    max: Some(1),
-                }))
+                    min,
+                    max,
+                    greedy,
+                }),
+                span,
+            );
+            if possessive {
+                ast = Ast::new(AstKind::AtomicGroup(Box::new(ast)), span);
             }
-            _ => Ok(ast),
         }
     }
 }
@@ -114,29 +444,75 @@ impl<'a> Parser<'a> {
 #[allow(dead_code)]
 fn print_ast(ast: &Ast, indent: usize) {
     let indent_str = " ".repeat(indent);
-    match ast {
-        Ast::Literal(c) => {
+    match &ast.kind {
+        AstKind::Empty => {
+            println!("{}Empty", indent_str);
+        }
+        AstKind::Literal(c) => {
             println!("{}Literal({})", indent_str, c);
         }
-        Ast::Concat(concat) => {
+        AstKind::Dot => {
+            println!("{}Dot", indent_str);
+        }
+        AstKind::PerlClass(class) => {
+            println!("{}PerlClass({:?})", indent_str, class);
+        }
+        AstKind::UnicodeClass(class) => {
+            println!("{}UnicodeClass({:?})", indent_str, class);
+        }
+        AstKind::Concat(concat) => {
             println!("{}Concat:", indent_str);
             concat.iter().for_each(|ast| print_ast(ast, indent + 2));
         }
-        Ast::Alternate(alternate) => {
+        AstKind::Alternate(alternate) => {
             println!("{}Alternate:", indent_str);
             alternate.iter().for_each(|ast| print_ast(ast, indent + 2));
         }
-        Ast::Repetition(repetition) => {
+        AstKind::Repetition(repetition) => {
             let max_str = match repetition.max {
                 Some(max) => max.to_string(),
                 None => "None".to_string(),
             };
             println!(
-                "{}Repetition(min: {}, max: {}):",
-                indent_str, repetition.min, max_str
+                "{}Repetition(min: {}, max: {}, greedy: {}):",
+                indent_str, repetition.min, max_str, repetition.greedy
             );
             print_ast(&repetition.ast, indent + 2);
         }
+        AstKind::Assertion(assertion) => {
+            println!("{}Assertion({:?})", indent_str, assertion);
+        }
+        AstKind::Capture(capture) => {
+            println!(
+                "{}Capture({}, name: {:?}):",
+                indent_str, capture.index, capture.name
+            );
+            print_ast(&capture.ast, indent + 2);
+        }
+        AstKind::Group(group) => {
+            println!("{}Group({:?}):", indent_str, group.flags);
+            print_ast(&group.ast, indent + 2);
+        }
+        AstKind::Lookaround(lookaround) => {
+            println!("{}Lookaround({:?}):", indent_str, lookaround.kind);
+            print_ast(&lookaround.ast, indent + 2);
+        }
+        AstKind::AtomicGroup(ast) => {
+            println!("{}AtomicGroup:", indent_str);
+            print_ast(ast, indent + 2);
+        }
+        AstKind::Backreference(index) => {
+            println!("{}Backreference({})", indent_str, index);
+        }
+        AstKind::Flags(flags) => {
+            println!("{}Flags({:?})", indent_str, flags);
+        }
+        AstKind::Class(class) => {
+            println!(
+                "{}Class(negated: {}, {:?})",
+                indent_str, class.negated, class.set
+            );
+        }
     }
 }
 
@@ -144,55 +520,555 @@ fn print_ast(ast: &Ast, indent: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::{ClassSetOpKind, LookaroundKind};
+    use crate::error::ErrorKind;
+
+    // 構文木の形だけを比べるため、範囲をすべて同じ値にする
+    const SPAN: Span = Span::splat(Position::new(0, 1, 1));
+
+    fn node(kind: AstKind) -> Ast {
+        Ast::new(kind, SPAN)
+    }
+
+    fn item(item: ClassItem) -> Spanned<ClassItem> {
+        Spanned::new(item, SPAN)
+    }
+
+    fn union(items: Vec<ClassItem>) -> Spanned<ClassSet> {
+        Spanned::new(ClassSet::Union(items.into_iter().map(item).collect()), SPAN)
+    }
+
+    fn without_spans(mut ast: Ast) -> Ast {
+        fn clear_class(class: &mut Class) {
+            class.set.span = SPAN;
+            clear_set(&mut class.set.value);
+        }
+
+        fn clear_set(set: &mut ClassSet) {
+            match set {
+                ClassSet::Union(items) => {
+                    for item in items {
+                        item.span = SPAN;
+                        if let ClassItem::Bracketed(class) = &mut item.value {
+                            clear_class(class);
+                        }
+                    }
+                }
+                ClassSet::BinaryOp(op) => {
+                    for set in [&mut op.lhs, &mut op.rhs] {
+                        set.span = SPAN;
+                        clear_set(&mut set.value);
+                    }
+                }
+            }
+        }
+
+        fn clear(ast: &mut Ast) {
+            ast.span = SPAN;
+            match &mut ast.kind {
+                AstKind::Concat(nodes) | AstKind::Alternate(nodes) => {
+                    nodes.iter_mut().for_each(clear)
+                }
+                AstKind::Repetition(Repetition { ast, .. })
+                | AstKind::Capture(Capture { ast, .. })
+                | AstKind::Group(Group { ast, .. })
+                | AstKind::Lookaround(Lookaround { ast, .. })
+                | AstKind::AtomicGroup(ast) => clear(ast),
+                AstKind::Class(class) => clear_class(class),
+                _ => {}
+            }
+        }
+
+        clear(&mut ast);
+        ast
+    }
 
     #[test]
     fn test_parser() {
         let mut parser = Parser::new("a|b");
-        let ast = parser.parse().unwrap();
+        let ast = parser.parse().map(without_spans).unwrap();
 
         // assertion
         assert_eq!(
             ast,
-            Ast::Alternate(vec![Ast::Literal('a'), Ast::Literal('b')])
+            node(AstKind::Alternate(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Literal('b'))
+            ]))
         );
 
         parser = Parser::new("a|b|c");
-        let ast = parser.parse().unwrap();
+        let ast = parser.parse().map(without_spans).unwrap();
         assert_eq!(
             ast,
-            Ast::Alternate(vec![
-                Ast::Literal('a'),
-                Ast::Literal('b'),
-                Ast::Literal('c')
-            ])
+            node(AstKind::Alternate(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Literal('b')),
+                node(AstKind::Literal('c'))
+            ]))
         );
 
         parser = Parser::new("a(bc|d)");
-        let ast = parser.parse().unwrap();
+        let ast = parser.parse().map(without_spans).unwrap();
         assert_eq!(
             ast,
-            Ast::Concat(vec![
-                Ast::Literal('a'),
-                Ast::Alternate(vec![
-                    Ast::Concat(vec![Ast::Literal('b'), Ast::Literal('c')]),
-                    Ast::Literal('d')
-                ])
-            ])
+            node(AstKind::Concat(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Capture(Capture {
+                    index: 1,
+                    name: None,
+                    ast: Box::new(node(AstKind::Alternate(vec![
+                        node(AstKind::Concat(vec![
+                            node(AstKind::Literal('b')),
+                            node(AstKind::Literal('c'))
+                        ])),
+                        node(AstKind::Literal('d'))
+                    ])))
+                }))
+            ]))
         );
 
         parser = Parser::new("((a|b)+)*");
-        let ast = parser.parse().unwrap();
+        let ast = parser.parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Repetition(Repetition {
+                ast: Box::new(node(AstKind::Capture(Capture {
+                    index: 1,
+                    name: None,
+                    ast: Box::new(node(AstKind::Repetition(Repetition {
+                        ast: Box::new(node(AstKind::Capture(Capture {
+                            index: 2,
+                            name: None,
+                            ast: Box::new(node(AstKind::Alternate(vec![
+                                node(AstKind::Literal('a')),
+                                node(AstKind::Literal('b'))
+                            ])))
+                        }))),
+                        min: 1,
+                        max: None,
+                        greedy: true
+                    })))
+                }))),
+                min: 0,
+                max: None,
+                greedy: true
+            }))
+        );
+
+        parser = Parser::new("^ab$");
+        let ast = parser.parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Assertion(Assertion::StartLine)),
+                node(AstKind::Literal('a')),
+                node(AstKind::Literal('b')),
+                node(AstKind::Assertion(Assertion::EndLine))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_named_capture() {
+        let mut parser = Parser::new("(?P<first>a)(b)(?<third>c)");
+        let ast = parser.parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Capture(Capture {
+                    index: 1,
+                    name: Some("first".to_string()),
+                    ast: Box::new(node(AstKind::Literal('a')))
+                })),
+                node(AstKind::Capture(Capture {
+                    index: 2,
+                    name: None,
+                    ast: Box::new(node(AstKind::Literal('b')))
+                })),
+                node(AstKind::Capture(Capture {
+                    index: 3,
+                    name: Some("third".to_string()),
+                    ast: Box::new(node(AstKind::Literal('c')))
+                })),
+            ]))
+        );
+        assert_eq!(
+            parser.capture_names(),
+            &[
+                None,
+                Some("first".to_string()),
+                None,
+                Some("third".to_string())
+            ]
+        );
+
+        let err = Parser::new("(?<a>x)|(?P<a>y)").parse().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::DuplicateGroupName("a".to_string()));
+
+        let err = Parser::new("(?<0>x)").parse().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidGroupName("0".to_string()));
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut parser = Parser::new("a(?i)b(?-i:c)");
+        let ast = parser.parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Flags(Flags {
+                    case_insensitive: Some(true),
+                    ..Flags::default()
+                })),
+                node(AstKind::Literal('b')),
+                node(AstKind::Group(Group {
+                    flags: Flags {
+                        case_insensitive: Some(false),
+                        ..Flags::default()
+                    },
+                    ast: Box::new(node(AstKind::Literal('c')))
+                })),
+            ]))
+        );
+        assert_eq!(parser.capture_names(), &[None]);
+
+        parser = Parser::new("(?x: a b )c d");
+        let ast = parser.parse().map(without_spans).unwrap();
+        let x = Flags {
+            ignore_whitespace: Some(true),
+            ..Flags::default()
+        };
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Group(Group {
+                    flags: x,
+                    ast: Box::new(node(AstKind::Concat(vec![
+                        node(AstKind::Literal('a')),
+                        node(AstKind::Literal('b'))
+                    ])))
+                })),
+                node(AstKind::Literal('c')),
+                node(AstKind::Literal(' ')),
+                node(AstKind::Literal('d')),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_class() {
+        let ast = Parser::new("[a-c_]x[^]-]")
+            .parse()
+            .map(without_spans)
+            .unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Class(Class {
+                    negated: false,
+                    set: union(vec![ClassItem::Range('a', 'c'), ClassItem::Literal('_')]),
+                })),
+                node(AstKind::Literal('x')),
+                node(AstKind::Class(Class {
+                    negated: true,
+                    set: union(vec![ClassItem::Literal(']'), ClassItem::Literal('-')]),
+                })),
+            ]))
+        );
+
+        let ast = Parser::new(r"[-a-\x7A\]*]+")
+            .parse()
+            .map(without_spans)
+            .unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Repetition(Repetition {
+                ast: Box::new(node(AstKind::Class(Class {
+                    negated: false,
+                    set: union(vec![
+                        ClassItem::Literal('-'),
+                        ClassItem::Range('a', 'z'),
+                        ClassItem::Literal(']'),
+                        ClassItem::Literal('*'),
+                    ]),
+                }))),
+                min: 1,
+                max: None,
+                greedy: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_class_set() {
+        let ast = Parser::new("[a-z--[aeiou]&&b-y]")
+            .parse()
+            .map(without_spans)
+            .unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Class(Class {
+                negated: false,
+                set: Spanned::new(
+                    ClassSet::BinaryOp(ClassSetBinaryOp {
+                        kind: ClassSetOpKind::Intersection,
+                        lhs: Box::new(Spanned::new(
+                            ClassSet::BinaryOp(ClassSetBinaryOp {
+                                kind: ClassSetOpKind::Difference,
+                                lhs: Box::new(union(vec![ClassItem::Range('a', 'z')])),
+                                rhs: Box::new(union(vec![ClassItem::Bracketed(Box::new(Class {
+                                    negated: false,
+                                    set: union(vec![
+                                        ClassItem::Literal('a'),
+                                        ClassItem::Literal('e'),
+                                        ClassItem::Literal('i'),
+                                        ClassItem::Literal('o'),
+                                        ClassItem::Literal('u'),
+                                    ]),
+                                }))])),
+                            }),
+                            SPAN,
+                        )),
+                        rhs: Box::new(union(vec![ClassItem::Range('b', 'y')])),
+                    }),
+                    SPAN,
+                ),
+            }))
+        );
+
+        let ast = Parser::new("[a[^b]]").parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Class(Class {
+                negated: false,
+                set: union(vec![
+                    ClassItem::Literal('a'),
+                    ClassItem::Bracketed(Box::new(Class {
+                        negated: true,
+                        set: union(vec![ClassItem::Literal('b')]),
+                    })),
+                ]),
+            }))
+        );
+
+        for pattern in ["[a&&b", "[a[b]", "[a--[b]"] {
+            assert!(Parser::new(pattern).parse().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_repetition() {
+        let ast = Parser::new("a{2,3}*").parse().map(without_spans).unwrap();
         assert_eq!(
             ast,
-            Ast::Repetition(Repetition {
-                ast: Box::new(Ast::Repetition(Repetition {
-                    ast: Box::new(Ast::Alternate(vec![Ast::Literal('a'), Ast::Literal('b')])),
+            node(AstKind::Repetition(Repetition {
+                ast: Box::new(node(AstKind::Repetition(Repetition {
+                    ast: Box::new(node(AstKind::Literal('a'))),
+                    min: 2,
+                    max: Some(3),
+                    greedy: true,
+                }))),
+                min: 0,
+                max: None,
+                greedy: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_lazy_repetition() {
+        let ast = Parser::new("a+?b??").parse().map(without_spans).unwrap();
+        assert_eq!(
+            ast,
+            node(AstKind::Concat(vec![
+                node(AstKind::Repetition(Repetition {
+                    ast: Box::new(node(AstKind::Literal('a'))),
                     min: 1,
-                    max: None
+                    max: None,
+                    greedy: false,
+                })),
+                node(AstKind::Repetition(Repetition {
+                    ast: Box::new(node(AstKind::Literal('b'))),
+                    min: 0,
+                    max: Some(1),
+                    greedy: false,
                 })),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parser_error() {
+        assert!(Parser::new("(a").parse().is_err());
+        assert!(Parser::new("a)").parse().is_err());
+        assert!(Parser::new("a|*").parse().is_err());
+        assert!(Parser::new("[a").parse().is_err());
+        assert!(Parser::new("[]").parse().is_err());
+        assert!(Parser::new("[z-a]").parse().is_err());
+        assert!(Parser::new("*").parse().is_err());
+        assert!(Parser::new("a||*").parse().is_err());
+    }
+
+    #[test]
+    fn test_parser_error_position() {
+        for (pattern, position) in [
+            ("a)", 1),
+            ("(a", 0),
+            ("a(?:b|(c)", 1),
+            ("a|*", 2),
+            ("[z-a]", 1),
+            ("[a", 0),
+            ("a[b[c]", 1),
+            ("(?<n>a)(?<n>b)", 7),
+            (r"\2(a)", 0),
+            (r"a\k<x>", 1),
+        ] {
+            let error = Parser::new(pattern).parse().err().unwrap();
+            assert_eq!(error.position(), Some(position), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_lookaround() {
+        let mut parser = Parser::new("a(?<!b|c)");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::Concat(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Lookaround(Lookaround {
+                    kind: LookaroundKind::NegativeLookbehind,
+                    ast: Box::new(node(AstKind::Alternate(vec![
+                        node(AstKind::Literal('b')),
+                        node(AstKind::Literal('c'))
+                    ]))),
+                })),
+            ])))
+        );
+
+        assert!(Parser::new("(?=a").parse().is_err());
+    }
+
+    #[test]
+    fn test_atomic_group() {
+        let repetition = |greedy| {
+            node(AstKind::Repetition(Repetition {
+                ast: Box::new(node(AstKind::Literal('a'))),
                 min: 0,
-                max: None
-            })
+                max: None,
+                greedy,
+            }))
+        };
+
+        let mut parser = Parser::new("(?>a*)");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::AtomicGroup(Box::new(repetition(true)))))
+        );
+
+        // 強欲な量指定子は繰り返しのアトミックグループと同じ
+        let mut parser = Parser::new("a*+");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::AtomicGroup(Box::new(repetition(true)))))
+        );
+
+        let mut parser = Parser::new("a*?+");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::Repetition(Repetition {
+                ast: Box::new(repetition(false)),
+                min: 1,
+                max: None,
+                greedy: true,
+            })))
+        );
+    }
+
+    #[test]
+    fn test_backreference() {
+        let mut parser = Parser::new(r"(?<w>a)\1\k<w>");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::Concat(vec![
+                node(AstKind::Capture(Capture {
+                    index: 1,
+                    name: Some("w".to_string()),
+                    ast: Box::new(node(AstKind::Literal('a'))),
+                })),
+                node(AstKind::Backreference(1)),
+                node(AstKind::Backreference(1)),
+            ])))
+        );
+
+        for pattern in [r"\1(a)", r"(a)\2", r"\k<w>(?<w>a)", r"(a)\k<b>"] {
+            assert!(Parser::new(pattern).parse().is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_span() {
+        let span = |start: (usize, usize, usize), end: (usize, usize, usize)| {
+            Span::new(
+                Position::new(start.0, start.1, start.2),
+                Position::new(end.0, end.1, end.2),
+            )
+        };
+
+        let ast = Parser::new("ab\n(c)+").parse().unwrap();
+        assert_eq!(ast.span, span((0, 1, 1), (7, 2, 5)));
+        let AstKind::Concat(nodes) = ast.kind else {
+            panic!("{:?}", ast);
+        };
+        assert_eq!(nodes[2].span, span((2, 1, 3), (3, 2, 1)));
+        assert_eq!(nodes[3].span, span((3, 2, 1), (7, 2, 5)));
+        let AstKind::Repetition(repetition) = &nodes[3].kind else {
+            panic!("{:?}", nodes[3]);
+        };
+        assert_eq!(repetition.ast.span, span((3, 2, 1), (6, 2, 4)));
+
+        // 空の選択肢は直前のトークンの終わりに位置する
+        let ast = Parser::new("é|").parse().unwrap();
+        assert_eq!(ast.span, span((0, 1, 1), (3, 1, 3)));
+        let AstKind::Alternate(nodes) = ast.kind else {
+            panic!("{:?}", ast);
+        };
+        assert_eq!(nodes[1].span, span((3, 1, 3), (3, 1, 3)));
+
+        // 読み飛ばした空白は範囲に含めない
+        let ast = Parser::new("(?x) [a]  ").parse().unwrap();
+        let AstKind::Concat(nodes) = ast.kind else {
+            panic!("{:?}", ast);
+        };
+        assert_eq!(nodes[1].span, span((5, 1, 6), (8, 1, 9)));
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            Parser::new("").parse().map(without_spans),
+            Ok(node(AstKind::Empty))
+        );
+        assert_eq!(
+            Parser::new("a|").parse().map(without_spans),
+            Ok(node(AstKind::Alternate(vec![
+                node(AstKind::Literal('a')),
+                node(AstKind::Empty)
+            ])))
+        );
+
+        let mut parser = Parser::new("(|b)");
+        assert_eq!(
+            parser.parse().map(without_spans),
+            Ok(node(AstKind::Capture(Capture {
+                index: 1,
+                name: None,
+                ast: Box::new(node(AstKind::Alternate(vec![
+                    node(AstKind::Empty),
+                    node(AstKind::Literal('b'))
+                ]))),
+            })))
         );
     }
 }
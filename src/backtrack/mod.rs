@@ -0,0 +1,370 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::LookaroundKind;
+use crate::error::Error;
+use crate::nfa::{self, TransitionKind, NFA};
+use crate::pikevm::Slots;
+use crate::unicode;
+
+// 1つの開始位置からの照合でたどる状態の数の上限の既定値
+pub const DEFAULT_BACKTRACK_LIMIT: usize = 1 << 24;
+
+// 状態、位置と、後方参照が参照するグループの位置の組み合わせに振った番号
+type VisitKey = (nfa::StateID, usize, usize);
+
+enum Frame {
+    Explore {
+        id: nfa::StateID,
+        at: usize,
+    },
+    Capture {
+        to_id: nfa::StateID,
+        at: usize,
+        slot: usize,
+    },
+    RestoreSlot {
+        slot: usize,
+        value: Option<usize>,
+    },
+    // 先読み・後読みやアトミックグループの中で記録したグループの位置を反映してから進む
+    Enter {
+        to_id: nfa::StateID,
+        at: usize,
+        slots: Slots,
+    },
+    RestoreSlots(Slots),
+}
+
+// 照合のたびに求め直さないよう、パターンのコンパイル時に一度だけ求めておくNFAの性質
+pub struct Analysis {
+    // 後方参照が参照するグループ。照合の結果は状態と位置とこれらのグループの位置だけで決まる
+    referenced_groups: Vec<usize>,
+    // 一度失敗した組み合わせを記録する状態。経路が合流する状態だけを記録すれば、
+    // 同じ組み合わせを繰り返したどることも、空の繰り返しが無限に続くことも防げる
+    join_states: Vec<bool>,
+    // 後方参照に到達しない状態からの照合の結果は、グループの位置によらない
+    reaches_backreference: Vec<bool>,
+}
+
+impl Analysis {
+    pub fn new(nfa: &NFA) -> Self {
+        Self {
+            referenced_groups: nfa.referenced_groups(),
+            join_states: nfa.join_states(),
+            reaches_backreference: nfa.reaches_backreference(),
+        }
+    }
+}
+
+// 優先度の高い経路から順に深さ優先でたどる。先読み・後読み、アトミックグループと後方参照も扱える
+pub struct Backtracker<'a> {
+    nfa: &'a NFA,
+    analysis: &'a Analysis,
+    // 参照されるグループの位置の組み合わせに振った番号。先頭からの組み合わせの番号と次の位置の組から
+    // 引く。0 はグループの位置を区別しないことを表す
+    slot_values: RefCell<HashMap<(usize, Option<usize>), usize>>,
+    limit: usize,
+    // 残りのたどれる状態の数。開始位置ごとに limit に戻す。
+    // 使い切ると照合を打ち切り、ErrorKind::BacktrackLimitExceeded を返す
+    fuel: Cell<usize>,
+    exhausted: Cell<bool>,
+}
+
+impl<'a> Backtracker<'a> {
+    pub fn new(nfa: &'a NFA, analysis: &'a Analysis, limit: usize) -> Self {
+        Self {
+            nfa,
+            analysis,
+            slot_values: RefCell::new(HashMap::new()),
+            limit,
+            fuel: Cell::new(limit),
+            exhausted: Cell::new(false),
+        }
+    }
+
+    // start 以降で最も左から始まるマッチのうち、優先度が最も高いもののスロットを返す。
+    // anchored の場合は start から始まるマッチのみを探す
+    pub fn find(
+        &self,
+        haystack: &str,
+        start: usize,
+        anchored: bool,
+    ) -> Result<Option<Slots>, Error> {
+        // ある位置である状態から受理できないことは開始位置によらないので、記録を共有する
+        let mut visited = HashSet::new();
+
+        for at in (start..=haystack.len()).filter(|&at| haystack.is_char_boundary(at)) {
+            let mut slots = vec![None; self.nfa.slots_len()];
+            slots[0] = Some(at);
+            self.fuel.set(self.limit);
+            let end = self.search(
+                haystack,
+                (self.nfa.start(), self.nfa.end()),
+                at,
+                None,
+                false,
+                &mut slots,
+                &mut visited,
+            );
+            if let Some(end) = end {
+                slots[1] = Some(end);
+                return Ok(Some(slots));
+            }
+            self.check_fuel()?;
+            if anchored {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    // テキスト全体にマッチするか
+    pub fn is_full_match(&self, haystack: &str) -> Result<bool, Error> {
+        let mut slots = vec![None; self.nfa.slots_len()];
+        let end = self.search(
+            haystack,
+            (self.nfa.start(), self.nfa.end()),
+            0,
+            Some(haystack.len()),
+            false,
+            &mut slots,
+            &mut HashSet::new(),
+        );
+        self.check_fuel()?;
+        Ok(end.is_some())
+    }
+
+    // 照合を打ち切った場合、その結果はマッチしなかったことを表さない
+    fn check_fuel(&self) -> Result<(), Error> {
+        if self.exhausted.get() {
+            return Err(Error::backtrack_limit_exceeded(self.limit));
+        }
+        Ok(())
+    }
+
+    // fragment の開始状態から at で照合を始め、終了状態に到達した位置を返す。
+    // end_at を指定した場合はその位置で終了状態に到達する経路のみを受け入れる。
+    // backward の場合はテキストを後ろへ読む
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        haystack: &str,
+        fragment: (nfa::StateID, nfa::StateID),
+        at: usize,
+        end_at: Option<usize>,
+        backward: bool,
+        slots: &mut Slots,
+        visited: &mut HashSet<VisitKey>,
+    ) -> Option<usize> {
+        let mut stack = vec![Frame::Explore { id: fragment.0, at }];
+
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Explore { id, at } => {
+                    // 一度失敗した組み合わせは再びたどらない
+                    let joins = id == fragment.0 || self.analysis.join_states[id];
+                    if joins && !visited.insert(self.visit_key(id, at, slots)) {
+                        continue;
+                    }
+                    if self.fuel.get() == 0 {
+                        self.exhausted.set(true);
+                        return None;
+                    }
+                    self.fuel.set(self.fuel.get() - 1);
+                    if id == fragment.1 && end_at.is_none_or(|end_at| end_at == at) {
+                        return Some(at);
+                    }
+
+                    let c = if backward {
+                        haystack[..at].chars().next_back()
+                    } else {
+                        haystack[at..].chars().next()
+                    };
+                    let transitions = self.nfa.state(id).unwrap().as_transitions();
+                    // 先に追加した遷移ほど優先度が高いので、逆順にスタックへ積む
+                    for transition in transitions.iter().rev() {
+                        let to_id = transition.to_id();
+                        match transition.kind() {
+                            TransitionKind::Literal(_) | TransitionKind::Ranges(_) => {
+                                if let Some(c) = c.filter(|&c| transition.kind().accepts(c)) {
+                                    let at = if backward {
+                                        at - c.len_utf8()
+                                    } else {
+                                        at + c.len_utf8()
+                                    };
+                                    stack.push(Frame::Explore { id: to_id, at });
+                                }
+                            }
+                            TransitionKind::Epsilon => stack.push(Frame::Explore { id: to_id, at }),
+                            TransitionKind::Look(look) => {
+                                if look.matches(haystack, at) {
+                                    stack.push(Frame::Explore { id: to_id, at });
+                                }
+                            }
+                            TransitionKind::Atomic { start, end } => {
+                                // 最初に見つかったマッチだけを使う
+                                let mut inner_slots = slots.clone();
+                                let end = self.search(
+                                    haystack,
+                                    (*start, *end),
+                                    at,
+                                    None,
+                                    backward,
+                                    &mut inner_slots,
+                                    &mut HashSet::new(),
+                                );
+                                if let Some(end) = end {
+                                    stack.push(Frame::Enter {
+                                        to_id,
+                                        at: end,
+                                        slots: inner_slots,
+                                    });
+                                }
+                            }
+                            TransitionKind::Backreference {
+                                group,
+                                case_insensitive,
+                            } => {
+                                let end = self.backreference(
+                                    haystack,
+                                    slots,
+                                    *group,
+                                    *case_insensitive,
+                                    at,
+                                    backward,
+                                );
+                                if let Some(end) = end {
+                                    stack.push(Frame::Explore { id: to_id, at: end });
+                                }
+                            }
+                            TransitionKind::Capture(slot) => stack.push(Frame::Capture {
+                                to_id,
+                                at,
+                                slot: *slot,
+                            }),
+                            TransitionKind::Lookaround { kind, start, end } => {
+                                if let Some(slots) =
+                                    self.lookaround(haystack, *kind, (*start, *end), at, slots)
+                                {
+                                    stack.push(Frame::Enter { to_id, at, slots });
+                                }
+                            }
+                        }
+                    }
+                }
+                Frame::Capture { to_id, at, slot } => {
+                    stack.push(Frame::RestoreSlot {
+                        slot,
+                        value: slots[slot],
+                    });
+                    slots[slot] = Some(at);
+                    stack.push(Frame::Explore { id: to_id, at });
+                }
+                Frame::RestoreSlot { slot, value } => {
+                    slots[slot] = value;
+                }
+                Frame::Enter {
+                    to_id,
+                    at,
+                    slots: mut entered,
+                } => {
+                    std::mem::swap(slots, &mut entered);
+                    stack.push(Frame::RestoreSlots(entered));
+                    stack.push(Frame::Explore { id: to_id, at });
+                }
+                Frame::RestoreSlots(saved) => {
+                    *slots = saved;
+                }
+            }
+        }
+
+        None
+    }
+
+    // 先読み・後読みが at で成立する場合、その後に使うスロットを返す。
+    // 否定の場合は内側で記録したグループの位置を捨てる
+    fn lookaround(
+        &self,
+        haystack: &str,
+        kind: LookaroundKind,
+        fragment: (nfa::StateID, nfa::StateID),
+        at: usize,
+        slots: &Slots,
+    ) -> Option<Slots> {
+        let mut inner_slots = slots.clone();
+        // 後読みの部分は後ろから読む順に構築されているので、at からテキストを後ろへ読む
+        let matched = self
+            .search(
+                haystack,
+                fragment,
+                at,
+                None,
+                kind.is_behind(),
+                &mut inner_slots,
+                &mut HashSet::new(),
+            )
+            .is_some();
+
+        match (matched, kind.is_negated()) {
+            (true, false) => Some(inner_slots),
+            (false, true) => Some(slots.clone()),
+            _ => None,
+        }
+    }
+
+    fn visit_key(&self, id: nfa::StateID, at: usize, slots: &Slots) -> VisitKey {
+        if !self.analysis.reaches_backreference[id] {
+            return (id, at, 0);
+        }
+
+        let mut slot_values = self.slot_values.borrow_mut();
+        let values = self
+            .analysis
+            .referenced_groups
+            .iter()
+            .flat_map(|&group| [slots[group * 2], slots[group * 2 + 1]])
+            .fold(0, |prefix, value| {
+                let next = slot_values.len() + 1;
+                *slot_values.entry((prefix, value)).or_insert(next)
+            });
+        (id, at, values)
+    }
+
+    // グループ group が記録した文字列が at から続く場合、その終わりの位置を返す。
+    // backward の場合は at の直前で終わるかを調べ、その始まりの位置を返す。
+    // グループが何も記録していない場合は失敗する
+    fn backreference(
+        &self,
+        haystack: &str,
+        slots: &Slots,
+        group: usize,
+        case_insensitive: bool,
+        at: usize,
+        backward: bool,
+    ) -> Option<usize> {
+        let start = (*slots.get(group * 2)?)?;
+        let end = (*slots.get(group * 2 + 1)?)?;
+        let matches =
+            |c: char, d: char| c == d || (case_insensitive && unicode::simple_fold(c).contains(&d));
+
+        if backward {
+            let mut rest = haystack[..at].chars();
+            for c in haystack[start..end].chars().rev() {
+                if !matches(c, rest.next_back()?) {
+                    return None;
+                }
+            }
+            Some(rest.as_str().len())
+        } else {
+            let mut rest = haystack[at..].chars();
+            for c in haystack[start..end].chars() {
+                if !matches(c, rest.next()?) {
+                    return None;
+                }
+            }
+            Some(haystack.len() - rest.as_str().len())
+        }
+    }
+}
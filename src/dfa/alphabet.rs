@@ -0,0 +1,138 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::interval::{self, IntervalSet};
+
+// 遷移で区別する必要のない文字をまとめた分類。どの集合についても、同じ分類の文字は
+// すべて含まれるか、すべて含まれないかのどちらかになる
+#[derive(Clone, Debug)]
+pub struct Alphabet {
+    ascii: [usize; 128],
+    // 各範囲とその分類を、範囲の昇順に保持する
+    ranges: Vec<(char, char, usize)>,
+    // 各分類に属する文字のうち最小のもの
+    representatives: Vec<char>,
+}
+
+impl Alphabet {
+    pub fn new<'a>(sets: impl IntoIterator<Item = &'a IntervalSet>) -> Self {
+        let sets: Vec<&IntervalSet> = sets
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // 各集合の範囲の端で文字全体を区切る
+        let boundary = |end: char| interval::increment(end).map_or(char::MAX as u32 + 1, u32::from);
+        let mut boundaries: BTreeSet<u32> = BTreeSet::from([0, char::MAX as u32 + 1]);
+        for set in &sets {
+            for &(start, end) in set.ranges() {
+                boundaries.insert(start as u32);
+                boundaries.insert(boundary(end));
+            }
+        }
+        let boundaries: Vec<u32> = boundaries.into_iter().collect();
+
+        // 区間ごとに、それを含む集合の組み合わせに応じて分類を細かくしていく
+        let mut classes = vec![0; boundaries.len() - 1];
+        for set in &sets {
+            let mut contained = vec![false; classes.len()];
+            for &(start, end) in set.ranges() {
+                let first = boundaries.binary_search(&(start as u32)).unwrap();
+                let last = boundaries.binary_search(&boundary(end)).unwrap();
+                contained[first..last].fill(true);
+            }
+            let mut refined = HashMap::new();
+            for (class, contained) in classes.iter_mut().zip(contained) {
+                let next = refined.len();
+                *class = *refined.entry((*class, contained)).or_insert(next);
+            }
+        }
+
+        // 分類の番号を文字の昇順に振り直し、隣接する同じ分類の区間を結合する
+        let mut renumbered = HashMap::new();
+        let mut ranges: Vec<(char, char, usize)> = Vec::new();
+        let mut representatives = Vec::new();
+        for (window, class) in boundaries.windows(2).zip(classes) {
+            // サロゲート領域にあたる区間には文字がない
+            let Some(start) = char::from_u32(window[0]) else {
+                continue;
+            };
+            let end = match char::from_u32(window[1]) {
+                Some(next) => interval::decrement(next),
+                None => char::MAX,
+            };
+            let class = *renumbered.entry(class).or_insert_with(|| {
+                representatives.push(start);
+                representatives.len() - 1
+            });
+            match ranges.last_mut() {
+                Some(last) if last.2 == class && interval::increment(last.1) == Some(start) => {
+                    last.1 = end
+                }
+                _ => ranges.push((start, end, class)),
+            }
+        }
+
+        let mut ascii = [0; 128];
+        for (c, class) in ascii.iter_mut().enumerate() {
+            *class = Self::search(&ranges, char::from(c as u8));
+        }
+
+        Self {
+            ascii,
+            ranges,
+            representatives,
+        }
+    }
+
+    // 分類の数
+    pub fn len(&self) -> usize {
+        self.representatives.len()
+    }
+
+    pub fn class_of(&self, c: char) -> usize {
+        match self.ascii.get(c as usize) {
+            Some(&class) => class,
+            None => Self::search(&self.ranges, c),
+        }
+    }
+
+    pub fn representatives(&self) -> &[char] {
+        &self.representatives
+    }
+
+    fn search(ranges: &[(char, char, usize)], c: char) -> usize {
+        let i = ranges.partition_point(|&(_, end, _)| end < c);
+        ranges[i].2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabet() {
+        let digits = IntervalSet::new([('0', '9')]);
+        let word = IntervalSet::new([('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]);
+        let alphabet = Alphabet::new([&digits, &word, &digits]);
+
+        // 数字、数字以外の単語構成文字、それ以外の3つに分かれる
+        assert_eq!(alphabet.len(), 3);
+        assert_eq!(alphabet.class_of('0'), alphabet.class_of('9'));
+        assert_eq!(alphabet.class_of('a'), alphabet.class_of('Z'));
+        assert_eq!(alphabet.class_of('_'), alphabet.class_of('a'));
+        assert_eq!(alphabet.class_of(' '), alphabet.class_of('é'));
+        assert_eq!(alphabet.class_of('\0'), alphabet.class_of(char::MAX));
+        assert_ne!(alphabet.class_of('0'), alphabet.class_of('a'));
+        assert_ne!(alphabet.class_of('a'), alphabet.class_of(' '));
+
+        for (class, &c) in alphabet.representatives().iter().enumerate() {
+            assert_eq!(alphabet.class_of(c), class);
+        }
+
+        let alphabet = Alphabet::new([]);
+        assert_eq!(alphabet.len(), 1);
+        assert_eq!(alphabet.class_of('\u{E000}'), 0);
+    }
+}
@@ -1,82 +1,298 @@
 use std::collections::{BTreeSet, HashMap, VecDeque};
 
-use super::{StateID, DFA};
+use super::alphabet::Alphabet;
+use super::{CharClass, StateID, DFA};
 use crate::error::Error;
-use crate::nfa::{self, TransitionKind, NFA};
+use crate::interval::IntervalSet;
+use crate::nfa::{self, Look, TransitionKind, NFA};
+use crate::unicode;
+
+// 開始位置ごとのNFAの状態の集合を開始位置の昇順に並べたものと、直前の文字の分類、
+// マッチが見つかったか。アンカーのあるDFAでは集合は1つだけになる
+type StateKey = (Vec<BTreeSet<nfa::StateID>>, Option<CharClass>, bool);
 
 pub struct Determinizer<'a> {
     nfa: &'a NFA,
     pub dfa: DFA,
+    anchored: bool,
+    // DFAの大きさの上限。大きさは各状態が持つNFAの状態の数と遷移表の要素の数の合計とする
+    size_limit: usize,
+    size: usize,
+    // 各状態が持つ、開始位置ごとのNFAの状態の集合
+    groups: Vec<Vec<Vec<nfa::StateID>>>,
+    // 各状態の直前の文字の分類。None はテキスト先頭を表す
+    behinds: Vec<Option<CharClass>>,
+    // 各状態に至るまでにマッチが見つかったか。見つかった後は新たにマッチを開始しない
+    found: Vec<bool>,
+    // NFA が含むアサーションの種類。判定に不要な文字の分類は区別しない
+    text: bool,
+    final_newline: bool,
+    line: bool,
+    crlf: bool,
+    word: bool,
+    unicode_word: bool,
 }
 
 impl<'a> Determinizer<'a> {
     pub fn new(nfa: &'a NFA) -> Self {
+        let (mut text, mut final_newline, mut line, mut crlf) = (false, false, false, false);
+        let (mut word, mut unicode_word) = (false, false);
+        for look in nfa.looks() {
+            match look {
+                Look::StartText | Look::EndText => text = true,
+                Look::EndTextOptionalNewline => (text, final_newline) = (true, true),
+                Look::StartLine | Look::EndLine => line = true,
+                Look::StartLineCRLF | Look::EndLineCRLF => (line, crlf) = (true, true),
+                Look::WordBoundary { unicode }
+                | Look::NotWordBoundary { unicode }
+                | Look::WordStart { unicode }
+                | Look::WordEnd { unicode } => {
+                    word = true;
+                    unicode_word |= unicode;
+                }
+            }
+        }
+
+        // アサーションの判定で区別する文字の集合
+        let mut look_sets = Vec::new();
+        if line || final_newline {
+            look_sets.push(IntervalSet::new([('\n', '\n')]));
+        }
+        if crlf {
+            look_sets.push(IntervalSet::new([('\r', '\r')]));
+        }
+        if word {
+            let ascii_word = IntervalSet::new([('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]);
+            if unicode_word {
+                let mut non_ascii_word = unicode::word();
+                non_ascii_word.difference(&ascii_word);
+                look_sets.push(non_ascii_word);
+            }
+            look_sets.push(ascii_word);
+        }
+
+        // NFAの遷移とアサーションのどちらでも区別されない文字は、同じ分類にまとめる
+        let mut literals = Vec::new();
+        let mut sets = Vec::new();
+        for id in 0..nfa.states_count() {
+            for transition in nfa.state(id).unwrap().as_transitions() {
+                match transition.kind() {
+                    TransitionKind::Literal(c) => literals.push(IntervalSet::new([(*c, *c)])),
+                    TransitionKind::Ranges(set) => sets.push(set),
+                    _ => {}
+                }
+            }
+        }
+        let alphabet = Alphabet::new(sets.into_iter().chain(&literals).chain(&look_sets));
+
         Self {
             nfa,
-            dfa: DFA::new(),
+            dfa: DFA::new(alphabet),
+            anchored: true,
+            size_limit: usize::MAX,
+            size: 0,
+            groups: Vec::new(),
+            behinds: Vec::new(),
+            found: Vec::new(),
+            text,
+            final_newline,
+            line,
+            crlf,
+            word,
+            unicode_word,
         }
     }
 
-    pub fn build(&mut self) -> Result<(), Error> {
-        let mut dfa_states: HashMap<BTreeSet<nfa::StateID>, StateID> = HashMap::new();
-        let mut queue: VecDeque<StateID> = VecDeque::new();
+    // false の場合、任意の位置から始まるマッチのうち最も左のものを探す DFA を構築する。
+    // 最も左のマッチが見つかると、それより後に開始したマッチの候補は捨て、新たなマッチも開始しない。
+    // そのため、遷移先のなくなった位置で最後に受理した位置が最も左のマッチの終了位置の1つになる
+    pub fn anchored(mut self, anchored: bool) -> Self {
+        self.anchored = anchored;
+        self
+    }
 
-        let start_state_ids = self.epsilon_closure(&[self.nfa.start()]);
-        let start_state_set: BTreeSet<nfa::StateID> = start_state_ids.iter().cloned().collect();
+    // 大きさが limit を超える場合は ErrorKind::DfaSizeLimitExceeded で構築を打ち切る
+    pub fn size_limit(mut self, limit: usize) -> Self {
+        self.size_limit = limit;
+        self
+    }
 
-        let is_match = start_state_ids.iter().any(|&id| self.nfa.is_accept(id));
-        let start_id = self.dfa.new_state(is_match, &start_state_ids);
+    pub fn build(&mut self) -> Result<(), Error> {
+        let mut dfa_states: HashMap<StateKey, StateID> = HashMap::new();
+        let mut queue: VecDeque<StateID> = VecDeque::new();
 
-        self.dfa.set_start(start_id);
-        dfa_states.insert(start_state_set, start_id);
-        queue.push_back(start_id);
+        // 開始状態は直前の文字の分類ごとに用意する
+        for behind in std::iter::once(None).chain(CharClass::ALL.map(Some)) {
+            let start_id = self.add_state(
+                vec![vec![self.nfa.start()]],
+                behind,
+                false,
+                &mut dfa_states,
+                &mut queue,
+            );
+            self.dfa.set_start(behind, start_id);
+        }
 
         while let Some(current_state_id) = queue.pop_front() {
             let transitions = self.get_transitions(current_state_id);
 
-            for (input, nfa_state_ids) in transitions {
-                let closure = self.epsilon_closure(&nfa_state_ids.into_iter().collect::<Vec<_>>());
-                let closure_set: BTreeSet<nfa::StateID> = closure.iter().cloned().collect();
+            for (column, groups, found) in transitions {
+                let behind = Some(self.dfa.columns()[column].1);
+                let to_state_id =
+                    self.add_state(groups, behind, found, &mut dfa_states, &mut queue);
+                self.dfa
+                    .add_transition(current_state_id, column, to_state_id);
+            }
+            if self.size > self.size_limit {
+                return Err(Error::dfa_size_limit_exceeded(self.size_limit));
+            }
+        }
 
-                let to_state_id = if let Some(&existing_id) = dfa_states.get(&closure_set) {
-                    existing_id
-                } else {
-                    let is_match = closure.iter().any(|&id| self.nfa.is_accept(id));
-                    let new_id = self.dfa.new_state(is_match, &closure);
+        Ok(())
+    }
 
-                    dfa_states.insert(closure_set, new_id);
-                    queue.push_back(new_id);
-                    new_id
-                };
+    fn add_state(
+        &mut self,
+        groups: Vec<Vec<nfa::StateID>>,
+        behind: Option<CharClass>,
+        found: bool,
+        dfa_states: &mut HashMap<StateKey, StateID>,
+        queue: &mut VecDeque<StateID>,
+    ) -> StateID {
+        let behind = self.normalize(behind);
+
+        // アサーションは次の文字が決まるまで判定できないため、ここではたどらない。
+        // 先に開始したマッチの候補と同じ状態は、後に開始したものから除く
+        let mut visited = vec![false; self.nfa.states_count()];
+        let closures: Vec<Vec<nfa::StateID>> = groups
+            .iter()
+            .map(|group| {
+                self.epsilon_closure(group, |_| false)
+                    .into_iter()
+                    .filter(|&id| !std::mem::replace(&mut visited[id], true))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|closure| !closure.is_empty())
+            .collect();
+        let key = (
+            closures
+                .iter()
+                .map(|closure| closure.iter().cloned().collect())
+                .collect(),
+            behind,
+            found,
+        );
+
+        if let Some(&existing_id) = dfa_states.get(&key) {
+            return existing_id;
+        }
 
-                self.dfa
-                    .add_transition(current_state_id, input, to_state_id);
+        let closure = closures.concat();
+        let is_match = closure.iter().any(|&id| self.nfa.is_accept(id));
+        let new_id = self.dfa.new_state(is_match, &closure);
+        self.size += closure.len() + self.dfa.columns().len();
+        self.groups.push(closures);
+        self.behinds.push(behind);
+        self.found.push(found);
+
+        // アサーションを経由して受理する場合は、次の文字の分類ごとに判定する
+        if !is_match {
+            let aheads = std::iter::once(None).chain(CharClass::ALL.map(Some));
+            for ahead in aheads {
+                let closure =
+                    self.epsilon_closure(&closure, |look| self.look_holds(look, behind, ahead));
+                if closure.iter().any(|&id| self.nfa.is_accept(id)) {
+                    self.dfa.add_look_match(new_id, ahead);
+                }
             }
         }
 
-        Ok(())
+        dfa_states.insert(key, new_id);
+        queue.push_back(new_id);
+        new_id
+    }
+
+    // アサーションの判定に影響しない区別をなくし、状態数を抑える
+    fn normalize(&self, class: Option<CharClass>) -> Option<CharClass> {
+        match class {
+            None if self.text || self.line => None,
+            // 逆向きのNFAでは、テキスト末尾の改行を読んだ直後の位置で \Z が成立する
+            Some(CharClass::FinalLineFeed) if self.final_newline && self.nfa.is_reversed() => {
+                Some(CharClass::FinalLineFeed)
+            }
+            Some(class) if class.is_line_feed() && self.line => Some(CharClass::LineFeed),
+            Some(CharClass::CarriageReturn) if self.crlf => Some(CharClass::CarriageReturn),
+            Some(CharClass::UnicodeWord) if self.unicode_word => Some(CharClass::UnicodeWord),
+            Some(CharClass::AsciiWord) if self.word => Some(CharClass::AsciiWord),
+            _ => Some(CharClass::Other),
+        }
     }
 
-    fn get_transitions(&self, state_id: StateID) -> HashMap<char, BTreeSet<nfa::StateID>> {
-        let mut transitions: HashMap<char, BTreeSet<nfa::StateID>> = HashMap::new();
-        let state = self.dfa.state(state_id).unwrap();
-
-        for &nfa_state_id in &state.nfa_states {
-            let transitions_from_state = self.nfa.state(nfa_state_id).unwrap().as_transitions();
-            for transition in transitions_from_state {
-                if let TransitionKind::Literal(c) = transition.kind() {
-                    transitions
-                        .entry(*c)
-                        .or_default()
-                        .insert(transition.to_id());
+    // 逆向きのNFAではテキストを末尾から走査するため、直前と直後を入れ替えて判定する
+    fn look_holds(&self, look: Look, behind: Option<CharClass>, ahead: Option<CharClass>) -> bool {
+        if self.nfa.is_reversed() {
+            look_holds(look, ahead, behind)
+        } else {
+            look_holds(look, behind, ahead)
+        }
+    }
+
+    // 状態から遷移表の各列の文字を読んだときの遷移先を、開始位置ごとのNFAの状態の集合として求める
+    fn get_transitions(&self, state_id: StateID) -> Vec<(usize, Vec<Vec<nfa::StateID>>, bool)> {
+        let groups = &self.groups[state_id];
+        let behind = self.behinds[state_id];
+        let found = self.found[state_id];
+
+        // 次の文字の分類ごとに、アサーションをたどった状態の集合を求める
+        let mut closures: HashMap<CharClass, (Vec<Vec<nfa::StateID>>, bool)> = HashMap::new();
+        let mut transitions = Vec::new();
+        for (column, &(c, ahead)) in self.dfa.columns().iter().enumerate() {
+            let (closures, matched) = closures.entry(ahead).or_insert_with(|| {
+                let holds = |look| self.look_holds(look, behind, Some(ahead));
+                let mut closures = Vec::new();
+                for group in groups {
+                    let closure = self.epsilon_closure(group, holds);
+                    let matched = closure.iter().any(|&id| self.nfa.is_accept(id));
+                    closures.push(closure);
+                    // 最も左のマッチが見つかった場合、それより後に開始した候補は捨てる
+                    if matched && !self.anchored {
+                        return (closures, true);
+                    }
                 }
+                (closures, false)
+            });
+
+            let mut targets: Vec<Vec<nfa::StateID>> = closures
+                .iter()
+                .map(|closure| {
+                    closure
+                        .iter()
+                        .flat_map(|&id| self.nfa.state(id).unwrap().as_transitions())
+                        .filter(|transition| transition.kind().accepts(c))
+                        .map(|transition| transition.to_id())
+                        .collect()
+                })
+                .collect();
+
+            // 非アンカーのDFAでは、マッチが見つかるまで各位置で新たにマッチを開始する
+            let found = found || *matched;
+            if !self.anchored && !found {
+                targets.push(vec![self.nfa.start()]);
+            }
+
+            if targets.iter().any(|ids| !ids.is_empty()) {
+                transitions.push((column, targets, found));
             }
         }
 
         transitions
     }
 
-    fn epsilon_closure(&self, start: &[nfa::StateID]) -> Vec<nfa::StateID> {
+    fn epsilon_closure(
+        &self,
+        start: &[nfa::StateID],
+        follows_look: impl Fn(Look) -> bool,
+    ) -> Vec<nfa::StateID> {
         let mut closure = Vec::new();
         let mut stack = start.to_vec();
         let mut visited = vec![false; self.nfa.states_count()];
@@ -88,7 +304,16 @@ impl<'a> Determinizer<'a> {
 
                 let transitions = self.nfa.state(state_id).unwrap().as_transitions();
                 for transition in transitions {
-                    if let nfa::TransitionKind::Epsilon = transition.kind() {
+                    let follows = match transition.kind() {
+                        TransitionKind::Epsilon | TransitionKind::Capture(_) => true,
+                        TransitionKind::Look(look) => follows_look(*look),
+                        TransitionKind::Literal(_)
+                        | TransitionKind::Ranges(_)
+                        | TransitionKind::Lookaround { .. }
+                        | TransitionKind::Atomic { .. }
+                        | TransitionKind::Backreference { .. } => false,
+                    };
+                    if follows {
                         stack.push(transition.to_id());
                     }
                 }
@@ -99,12 +324,44 @@ impl<'a> Determinizer<'a> {
     }
 }
 
+// 前後の文字の分類からアサーションが成立するかを判定する。None はテキストの端を表す
+fn look_holds(look: Look, behind: Option<CharClass>, ahead: Option<CharClass>) -> bool {
+    let line_feed = |class: Option<CharClass>| class.is_some_and(CharClass::is_line_feed);
+    match look {
+        Look::StartText => behind.is_none(),
+        Look::EndText => ahead.is_none(),
+        Look::EndTextOptionalNewline => matches!(ahead, None | Some(CharClass::FinalLineFeed)),
+        Look::StartLine => behind.is_none() || line_feed(behind),
+        Look::EndLine => ahead.is_none() || line_feed(ahead),
+        Look::StartLineCRLF => match behind {
+            None => true,
+            Some(class) if class.is_line_feed() => true,
+            Some(CharClass::CarriageReturn) => !line_feed(ahead),
+            Some(_) => false,
+        },
+        Look::EndLineCRLF => match ahead {
+            None | Some(CharClass::CarriageReturn) => true,
+            Some(class) if class.is_line_feed() => behind != Some(CharClass::CarriageReturn),
+            Some(_) => false,
+        },
+        Look::WordBoundary { unicode }
+        | Look::NotWordBoundary { unicode }
+        | Look::WordStart { unicode }
+        | Look::WordEnd { unicode } => look.matches_word(
+            behind.is_some_and(|class| class.is_word(unicode)),
+            ahead.is_some_and(|class| class.is_word(unicode)),
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
 
     use super::super::State;
     use super::*;
+    use crate::ast::Flags;
+    use crate::ast::Parser;
     use crate::nfa::TransitionKind;
     #[test]
     fn test_build() {
@@ -166,7 +423,7 @@ mod tests {
             State {
                 id: 1,
                 is_match: true,
-                nfa_states: vec![3, 1],
+                nfa_states: vec![1, 3],
             },
             State {
                 id: 2,
@@ -199,11 +456,86 @@ mod tests {
 
         // 遷移が一致するかを確認
         for (from, input, to) in expected_transitions {
-            assert_eq!(dfa.next(from, input), Some(to));
+            assert_eq!(dfa.next(from, dfa.column(input, false)), Some(to));
         }
 
         // 受理状態が一致するかを確認
         let expected_accepts: Vec<StateID> = expected_states.iter().map(|s| s.id).collect();
         assert_eq!(dfa.accepts(), expected_accepts);
     }
+
+    #[test]
+    fn test_word_boundary() {
+        let ast = Parser::new(r"a\b").parse().unwrap();
+        let mut nfa = NFA::new();
+        nfa.build(&ast, &Flags::default()).unwrap();
+
+        let mut determinizer = Determinizer::new(&nfa);
+        determinizer.build().unwrap();
+        let dfa = &determinizer.dfa;
+
+        // a の後に単語構成文字が続く場合は受理しない
+        let column = |c| dfa.column(c, false);
+        let state = dfa.next(dfa.start(None).unwrap(), column('a')).unwrap();
+        assert!(!dfa.is_match(state));
+        assert!(dfa.is_match_before(state, None));
+        assert!(dfa.is_match_before(state, Some(column(' '))));
+        assert!(!dfa.is_match_before(state, Some(column('b'))));
+        assert!(!dfa.is_match_before(state, Some(column('é'))));
+    }
+
+    #[test]
+    fn test_start_line() {
+        let ast = Parser::new(r"^a").parse().unwrap();
+        let mut nfa = NFA::new();
+        let flags = Flags {
+            multi_line: Some(true),
+            ..Flags::default()
+        };
+        nfa.build(&ast, &flags).unwrap();
+
+        let mut determinizer = Determinizer::new(&nfa);
+        determinizer.build().unwrap();
+        let dfa = &determinizer.dfa;
+
+        // テキスト先頭と途中の位置の開始状態は区別される
+        let column = |c| dfa.column(c, false);
+        let start = dfa.start(None).unwrap();
+        let mid_start = dfa.start(Some(column('b'))).unwrap();
+        assert_ne!(start, mid_start);
+        assert!(dfa
+            .next(start, column('a'))
+            .is_some_and(|id| dfa.is_match(id)));
+        assert_eq!(dfa.next(mid_start, column('a')), None);
+
+        // 改行の直後は行頭になる
+        let reverse_nfa = nfa.reverse();
+        let mut reverse_determinizer = Determinizer::new(&reverse_nfa);
+        reverse_determinizer.build().unwrap();
+        let dfa = &reverse_determinizer.dfa;
+        let column = |c| dfa.column(c, false);
+        let state = dfa.next(dfa.start(None).unwrap(), column('a')).unwrap();
+        assert!(dfa.is_match_before(state, Some(column('\n'))));
+        assert!(dfa.is_match_before(state, None));
+        assert!(!dfa.is_match_before(state, Some(column('b'))));
+    }
+
+    #[test]
+    fn test_leftmost() {
+        let ast = Parser::new(r"ab|bcdef").parse().unwrap();
+        let mut nfa = NFA::new();
+        nfa.build(&ast, &Flags::default()).unwrap();
+
+        let mut determinizer = Determinizer::new(&nfa).anchored(false);
+        determinizer.build().unwrap();
+        let dfa = &determinizer.dfa;
+
+        // 最も左のマッチ ab が見つかると、後から開始した bcdef の候補は捨てる
+        let mut state = dfa.start(None).unwrap();
+        for c in "xab".chars() {
+            state = dfa.next(state, dfa.column(c, false)).unwrap();
+        }
+        assert!(dfa.is_match(state));
+        assert_eq!(dfa.next(state, dfa.column('c', false)), None);
+    }
 }
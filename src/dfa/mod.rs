@@ -1,12 +1,69 @@
+pub mod alphabet;
 pub mod determinize;
+pub mod search;
 
 use std::collections::HashMap;
 use std::hash::Hash;
 
 use crate::nfa;
+use alphabet::Alphabet;
 
 pub type StateID = usize;
 
+// 1つのDFAの大きさの上限の既定値
+pub const DEFAULT_SIZE_LIMIT: usize = 50_000;
+
+// 行頭や単語境界などのアサーションを判定するために区別する文字の分類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    LineFeed,       // \n
+    FinalLineFeed,  // テキスト末尾の \n。直前の位置で \Z が成立する
+    CarriageReturn, // \r
+    AsciiWord,      // ASCII の英数字と '_'
+    UnicodeWord,    // それ以外の \w の Unicode 版に含まれる文字
+    Other,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 6] = [
+        CharClass::LineFeed,
+        CharClass::FinalLineFeed,
+        CharClass::CarriageReturn,
+        CharClass::AsciiWord,
+        CharClass::UnicodeWord,
+        CharClass::Other,
+    ];
+
+    pub fn of(c: char) -> Self {
+        if c == '\n' {
+            CharClass::LineFeed
+        } else if c == '\r' {
+            CharClass::CarriageReturn
+        } else if nfa::is_word_char(c, false) {
+            CharClass::AsciiWord
+        } else if nfa::is_word_char(c, true) {
+            CharClass::UnicodeWord
+        } else {
+            CharClass::Other
+        }
+    }
+
+    pub fn is_word(self, unicode: bool) -> bool {
+        match self {
+            CharClass::AsciiWord => true,
+            CharClass::UnicodeWord => unicode,
+            CharClass::LineFeed
+            | CharClass::FinalLineFeed
+            | CharClass::CarriageReturn
+            | CharClass::Other => false,
+        }
+    }
+
+    pub fn is_line_feed(self) -> bool {
+        matches!(self, CharClass::LineFeed | CharClass::FinalLineFeed)
+    }
+}
+
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 pub struct State {
     pub id: StateID,
@@ -18,25 +75,60 @@ pub struct State {
 #[derive(Debug)]
 pub struct DFA {
     states: Vec<State>,
-    start: Option<StateID>,
-    transitions: HashMap<StateID, HashMap<char, StateID>>,
+    // 直前の文字の分類ごとの開始状態。None はテキスト先頭から走査を始める場合を表す
+    starts: HashMap<Option<CharClass>, StateID>,
+    alphabet: Alphabet,
+    // 遷移表の各列が表す文字と、その文字の分類。アルファベットの各分類の後に、
+    // テキスト末尾の改行を表す列を置く
+    columns: Vec<(char, CharClass)>,
+    // 各状態から各列の文字を読んだときの遷移先。状態ごとに列の数だけ並べる
+    transitions: Vec<Option<StateID>>,
+    // 各状態が次の文字の分類によって受理するか。テキスト末尾と CharClass ごとに1ビットを使う
+    look_matches: Vec<u8>,
 }
 
 impl DFA {
-    pub fn new() -> Self {
+    pub fn new(alphabet: Alphabet) -> Self {
+        let columns = alphabet
+            .representatives()
+            .iter()
+            .map(|&c| (c, CharClass::of(c)))
+            .chain([('\n', CharClass::FinalLineFeed)])
+            .collect();
         Self {
             states: Vec::new(),
-            start: None,
-            transitions: HashMap::new(),
+            starts: HashMap::new(),
+            alphabet,
+            columns,
+            transitions: Vec::new(),
+            look_matches: Vec::new(),
+        }
+    }
+
+    pub fn set_start(&mut self, behind: Option<CharClass>, state_id: StateID) {
+        self.starts.insert(behind, state_id);
+    }
+
+    pub fn columns(&self) -> &[(char, CharClass)] {
+        &self.columns
+    }
+
+    // 文字 c を読むときに使う遷移表の列。last は c がテキスト末尾の文字であることを表す
+    pub fn column(&self, c: char, last: bool) -> usize {
+        if last && c == '\n' {
+            self.alphabet.len()
+        } else {
+            self.alphabet.class_of(c)
         }
     }
 
-    pub fn set_start(&mut self, state_id: StateID) {
-        self.start = Some(state_id);
+    // 列 column の文字を読むと from から to へ遷移する
+    pub fn add_transition(&mut self, from: StateID, column: usize, to: StateID) {
+        self.transitions[from * self.columns.len() + column] = Some(to);
     }
 
-    pub fn add_transition(&mut self, from: StateID, input: char, to: StateID) {
-        self.transitions.entry(from).or_default().insert(input, to);
+    pub fn add_look_match(&mut self, id: StateID, next: Option<CharClass>) {
+        self.look_matches[id] |= look_bit(next);
     }
 
     pub fn new_state(&mut self, is_match: bool, nfa_states: &[nfa::StateID]) -> StateID {
@@ -49,17 +141,40 @@ impl DFA {
         };
 
         self.states.push(state);
+        self.transitions
+            .extend(std::iter::repeat_n(None, self.columns.len()));
+        self.look_matches.push(0);
 
         id
     }
 
+    #[allow(dead_code)]
     pub fn state(&self, id: StateID) -> Option<&State> {
         self.states.get(id)
     }
 
-    pub fn start(&self) -> Option<StateID> {
-        self.start
+    // 直前に読んだ文字の列が behind の位置から走査を始めるときの開始状態。
+    // None はテキストの端から走査を始める場合を表す
+    pub fn start(&self, behind: Option<usize>) -> Option<StateID> {
+        let behind = behind.map(|column| self.columns[column].1);
+        self.starts.get(&behind).copied()
+    }
+
+    pub fn is_match(&self, id: StateID) -> bool {
+        self.states.get(id).is_some_and(|state| state.is_match)
+    }
+
+    // 次に読む文字の列が next のときに受理するか。テキストの端では next を None とする
+    pub fn is_match_before(&self, id: StateID, next: Option<usize>) -> bool {
+        if self.is_match(id) {
+            return true;
+        }
+        let next = next.map(|column| self.columns[column].1);
+        self.look_matches
+            .get(id)
+            .is_some_and(|&bits| bits & look_bit(next) != 0)
     }
+
     pub fn accepts(&self) -> Vec<StateID> {
         self.states
             .iter()
@@ -68,11 +183,11 @@ impl DFA {
             .collect()
     }
 
-    pub fn next(&self, current: StateID, input: char) -> Option<StateID> {
+    pub fn next(&self, current: StateID, column: usize) -> Option<StateID> {
         self.transitions
-            .get(&current)
-            .and_then(|transitions| transitions.get(&input))
-            .cloned()
+            .get(current * self.columns.len() + column)
+            .copied()
+            .flatten()
     }
 
     #[allow(dead_code)]
@@ -82,38 +197,70 @@ impl DFA {
             println!("  is_match: {}", state.is_match);
             println!("  nfa_states: {:?}", state.nfa_states);
             println!("  transitions:");
-            if let Some(transitions) = self.transitions.get(&state.id) {
-                for (input, to) in transitions {
-                    println!("    input:{} -> {}", input, to);
+            for (column, &(c, class)) in self.columns.iter().enumerate() {
+                if let Some(to) = self.transitions[i * self.columns.len() + column] {
+                    println!("    input:{:?} ({:?}) -> {}", c, class, to);
                 }
             }
         }
+        println!("Start states: {:?}", self.starts);
         println!("Accept states: {:?}", self.accepts());
+        println!("Look accept states: {:?}", self.look_matches);
+    }
+}
+
+fn look_bit(next: Option<CharClass>) -> u8 {
+    match next {
+        None => 1,
+        Some(class) => 1 << (class as u8 + 1),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::interval::IntervalSet;
 
     #[test]
     fn test_dfa() {
-        let mut dfa: DFA = DFA::new();
+        let a = IntervalSet::new([('a', 'a')]);
+        let b = IntervalSet::new([('b', 'b')]);
+        let x_to_z = IntervalSet::new([('x', 'z')]);
+        let alphabet = Alphabet::new([&a, &b, &x_to_z]);
+        let mut dfa: DFA = DFA::new(alphabet);
+        let column = |dfa: &DFA, c| dfa.column(c, false);
 
         let state0 = dfa.new_state(false, &[0]);
         let state1 = dfa.new_state(false, &[1]);
         let state2 = dfa.new_state(true, &[2]);
 
-        dfa.set_start(state0);
+        dfa.set_start(None, state0);
 
-        dfa.add_transition(state0, 'a', state1);
-        dfa.add_transition(state1, 'b', state2);
+        dfa.add_transition(state0, column(&dfa, 'a'), state1);
+        dfa.add_transition(state1, column(&dfa, 'b'), state2);
+        dfa.add_transition(state1, column(&dfa, 'x'), state0);
 
-        assert_eq!(dfa.start(), Some(state0));
+        assert_eq!(dfa.start(None), Some(state0));
         assert_eq!(dfa.accepts(), vec![state2]);
 
-        assert_eq!(dfa.next(state0, 'a'), Some(state1));
-        assert_eq!(dfa.next(state1, 'b'), Some(state2));
-        assert_eq!(dfa.next(state0, 'b'), None);
+        assert_eq!(dfa.next(state0, column(&dfa, 'a')), Some(state1));
+        assert_eq!(dfa.next(state1, column(&dfa, 'b')), Some(state2));
+        assert_eq!(dfa.next(state0, column(&dfa, 'b')), None);
+        assert_eq!(dfa.next(state1, column(&dfa, 'y')), Some(state0));
+        assert_eq!(dfa.next(state1, column(&dfa, 'c')), None);
+    }
+
+    #[test]
+    fn test_final_line_feed_column() {
+        let alphabet = Alphabet::new([&IntervalSet::new([('\n', '\n')])]);
+        let dfa: DFA = DFA::new(alphabet);
+
+        // テキスト末尾の改行だけは別の列で読む
+        let line_feed = dfa.column('\n', false);
+        let final_line_feed = dfa.column('\n', true);
+        assert_ne!(line_feed, final_line_feed);
+        assert_eq!(dfa.columns()[line_feed].1, CharClass::LineFeed);
+        assert_eq!(dfa.columns()[final_line_feed].1, CharClass::FinalLineFeed);
+        assert_eq!(dfa.column('a', true), dfa.column('a', false));
     }
 }
@@ -0,0 +1,123 @@
+use super::determinize::Determinizer;
+use super::DFA;
+use crate::error::Error;
+use crate::nfa::NFA;
+
+// 用途の異なる3つのDFAを使ってテキストを走査する
+pub struct Searcher {
+    forward: DFA,
+    leftmost: DFA,
+    reverse: DFA,
+}
+
+impl Searcher {
+    // 各DFAの大きさは size_limit までとする
+    pub fn new(nfa: &NFA, size_limit: usize) -> Result<Self, Error> {
+        let mut determinizer = Determinizer::new(nfa).size_limit(size_limit);
+        determinizer.build()?;
+
+        let mut leftmost_determinizer = Determinizer::new(nfa)
+            .anchored(false)
+            .size_limit(size_limit);
+        leftmost_determinizer.build()?;
+
+        // マッチの終了位置から開始位置を求めるため、逆向きのNFAからDFAを構築する
+        let reverse_nfa = nfa.reverse();
+        let mut reverse_determinizer = Determinizer::new(&reverse_nfa).size_limit(size_limit);
+        reverse_determinizer.build()?;
+
+        Ok(Self {
+            forward: determinizer.dfa,
+            leftmost: leftmost_determinizer.dfa,
+            reverse: reverse_determinizer.dfa,
+        })
+    }
+
+    // テキスト中のいずれかの部分文字列にマッチするか
+    pub fn is_match(&self, text: &str) -> bool {
+        let mut current_state = self.leftmost.start(None).unwrap();
+        for (i, c) in text.char_indices() {
+            let column = self.leftmost.column(c, i + c.len_utf8() == text.len());
+            if self.leftmost.is_match_before(current_state, Some(column)) {
+                return true;
+            }
+            match self.leftmost.next(current_state, column) {
+                Some(state) => current_state = state,
+                None => return false,
+            }
+        }
+        self.leftmost.is_match_before(current_state, None)
+    }
+
+    // テキスト全体にマッチするか
+    pub fn is_full_match(&self, text: &str) -> bool {
+        let mut current_state = self.forward.start(None).unwrap();
+        for (i, c) in text.char_indices() {
+            let column = self.forward.column(c, i + c.len_utf8() == text.len());
+            match self.forward.next(current_state, column) {
+                Some(state) => current_state = state,
+                None => return false,
+            }
+        }
+        self.forward.is_match_before(current_state, None)
+    }
+
+    // 最も左のマッチの開始位置を求める
+    pub fn find_start(&self, haystack: &str) -> Option<usize> {
+        let end = self.find_end(haystack)?;
+        self.rfind_start(haystack, end)
+    }
+
+    // 先頭から走査し、最も左のマッチの終了位置を求める。
+    // 最も左のマッチが終わると遷移先がなくなるため、そこで走査を止める
+    fn find_end(&self, haystack: &str) -> Option<usize> {
+        let mut current_state = self.leftmost.start(None).unwrap();
+        let mut end = None;
+
+        for (i, c) in haystack.char_indices() {
+            let column = self.leftmost.column(c, i + c.len_utf8() == haystack.len());
+            if self.leftmost.is_match_before(current_state, Some(column)) {
+                end = Some(i);
+            }
+            match self.leftmost.next(current_state, column) {
+                Some(state) => current_state = state,
+                None => return end,
+            }
+        }
+
+        if self.leftmost.is_match_before(current_state, None) {
+            end = Some(haystack.len());
+        }
+
+        end
+    }
+
+    // 終了位置 end から逆向きに走査し、end で終わるマッチの最も左の開始位置を求める
+    fn rfind_start(&self, haystack: &str, end: usize) -> Option<usize> {
+        // 逆向きの走査では end の直後の文字が直前の文字にあたる
+        let behind = haystack[end..]
+            .chars()
+            .next()
+            .map(|c| self.reverse.column(c, end + c.len_utf8() == haystack.len()));
+        let mut current_state = self.reverse.start(behind)?;
+        let mut start = None;
+
+        for (i, c) in haystack[..end].char_indices().rev() {
+            let column = self.reverse.column(c, i + c.len_utf8() == haystack.len());
+            if self.reverse.is_match_before(current_state, Some(column)) {
+                start = Some(i + c.len_utf8());
+            }
+            match self.reverse.next(current_state, column) {
+                Some(state) => current_state = state,
+                None => return start,
+            }
+        }
+
+        // 逆向きの走査ではテキスト先頭が末尾にあたる
+        if self.reverse.is_match_before(current_state, None) {
+            start = Some(0);
+        }
+
+        start
+    }
+}
@@ -1,14 +1,25 @@
-#[derive(Clone, Debug)]
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Error {
     kind: ErrorKind,
+    position: Option<usize>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ErrorKind {
     SyntaxError(String),
     ParseError(String),
+    InvalidGroupName(String),
+    DuplicateGroupName(String),
     StateIDOverflow { max: usize },
     InvalidState(String),
+    SizeLimitExceeded { limit: usize },
+    DfaSizeLimitExceeded { limit: usize },
+    UnknownPropertyName(String),
+    UnknownPropertyValue(String),
+    Unsupported(String),
+    BacktrackLimitExceeded { limit: usize },
 }
 
 impl Error {
@@ -16,21 +27,125 @@ impl Error {
         &self.kind
     }
 
+    // エラーの原因となったパターン中の位置(バイト単位)
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub(crate) fn at(mut self, position: usize) -> Self {
+        self.position.get_or_insert(position);
+        self
+    }
+
     pub(crate) fn syntax(message: &str) -> Self {
         Self {
             kind: ErrorKind::SyntaxError(message.to_string()),
+            position: None,
         }
     }
 
     pub(crate) fn parse(message: &str) -> Self {
         Self {
             kind: ErrorKind::ParseError(message.to_string()),
+            position: None,
+        }
+    }
+
+    pub(crate) fn invalid_group_name(name: &str) -> Self {
+        Self {
+            kind: ErrorKind::InvalidGroupName(name.to_string()),
+            position: None,
+        }
+    }
+
+    pub(crate) fn duplicate_group_name(name: &str) -> Self {
+        Self {
+            kind: ErrorKind::DuplicateGroupName(name.to_string()),
+            position: None,
         }
     }
 
     pub(crate) fn state_id_overflow(max: usize) -> Self {
         Self {
             kind: ErrorKind::StateIDOverflow { max },
+            position: None,
+        }
+    }
+
+    pub(crate) fn unknown_property_name(name: &str) -> Self {
+        Self {
+            kind: ErrorKind::UnknownPropertyName(name.to_string()),
+            position: None,
         }
     }
+
+    pub(crate) fn unknown_property_value(value: &str) -> Self {
+        Self {
+            kind: ErrorKind::UnknownPropertyValue(value.to_string()),
+            position: None,
+        }
+    }
+
+    pub(crate) fn unsupported(message: &str) -> Self {
+        Self {
+            kind: ErrorKind::Unsupported(message.to_string()),
+            position: None,
+        }
+    }
+
+    pub(crate) fn backtrack_limit_exceeded(limit: usize) -> Self {
+        Self {
+            kind: ErrorKind::BacktrackLimitExceeded { limit },
+            position: None,
+        }
+    }
+
+    pub(crate) fn size_limit_exceeded(limit: usize) -> Self {
+        Self {
+            kind: ErrorKind::SizeLimitExceeded { limit },
+            position: None,
+        }
+    }
+
+    pub(crate) fn dfa_size_limit_exceeded(limit: usize) -> Self {
+        Self {
+            kind: ErrorKind::DfaSizeLimitExceeded { limit },
+            position: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::SyntaxError(message) => write!(f, "syntax error: {}", message)?,
+            ErrorKind::ParseError(message) => write!(f, "parse error: {}", message)?,
+            ErrorKind::InvalidGroupName(name) => write!(f, "invalid group name: '{}'", name)?,
+            ErrorKind::DuplicateGroupName(name) => write!(f, "duplicate group name: '{}'", name)?,
+            ErrorKind::StateIDOverflow { max } => write!(f, "state id overflow (max: {})", max)?,
+            ErrorKind::InvalidState(message) => write!(f, "invalid state: {}", message)?,
+            ErrorKind::UnknownPropertyName(name) => {
+                write!(f, "unknown Unicode property name: '{}'", name)?
+            }
+            ErrorKind::UnknownPropertyValue(value) => {
+                write!(f, "unknown Unicode property value: '{}'", value)?
+            }
+            ErrorKind::Unsupported(message) => write!(f, "unsupported: {}", message)?,
+            ErrorKind::BacktrackLimitExceeded { limit } => {
+                write!(f, "backtrack limit exceeded ({} steps)", limit)?
+            }
+            ErrorKind::SizeLimitExceeded { limit } => {
+                write!(f, "compiled pattern exceeds size limit ({} states)", limit)?
+            }
+            ErrorKind::DfaSizeLimitExceeded { limit } => {
+                write!(f, "DFA exceeds size limit ({})", limit)?
+            }
+        }
+        if let Some(position) = self.position {
+            write!(f, " at position {}", position)?;
+        }
+        Ok(())
+    }
 }
+
+impl std::error::Error for Error {}
@@ -0,0 +1,181 @@
+// 互いに重ならず隣接もしない文字の範囲を昇順に保持する集合
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    ranges: Vec<(char, char)>,
+}
+
+impl IntervalSet {
+    pub fn new(ranges: impl IntoIterator<Item = (char, char)>) -> Self {
+        let mut set = Self {
+            ranges: ranges.into_iter().collect(),
+        };
+        set.canonicalize();
+        set
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        let i = self.ranges.partition_point(|&(_, end)| end < c);
+        self.ranges.get(i).is_some_and(|&(start, _)| start <= c)
+    }
+
+    pub fn union(&mut self, other: &IntervalSet) {
+        self.ranges.extend_from_slice(&other.ranges);
+        self.canonicalize();
+    }
+
+    pub fn intersect(&mut self, other: &IntervalSet) {
+        let mut intersection = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while let (Some(&a), Some(&b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.0.max(b.0);
+            let end = a.1.min(b.1);
+            if start <= end {
+                intersection.push((start, end));
+            }
+            // 先に終わる方を進める
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        self.ranges = intersection;
+    }
+
+    pub fn difference(&mut self, other: &IntervalSet) {
+        let mut negated = other.clone();
+        negated.negate();
+        self.intersect(&negated);
+    }
+
+    // どちらか一方のみに含まれる文字の集合にする
+    pub fn symmetric_difference(&mut self, other: &IntervalSet) {
+        let mut intersection = self.clone();
+        intersection.intersect(other);
+        self.union(other);
+        self.difference(&intersection);
+    }
+
+    // すべての Unicode スカラー値に対する補集合にする
+    pub fn negate(&mut self) {
+        let mut negated = Vec::new();
+        let mut next = Some('\0');
+
+        for &(start, end) in &self.ranges {
+            if let Some(next) = next {
+                if next < start {
+                    negated.push((next, decrement(start)));
+                }
+            }
+            next = increment(end);
+        }
+        if let Some(next) = next {
+            negated.push((next, char::MAX));
+        }
+
+        self.ranges = negated;
+    }
+
+    // 範囲を昇順に並べ、重なるものや隣接するものを結合する
+    fn canonicalize(&mut self) {
+        self.ranges.retain(|&(start, end)| start <= end);
+        self.ranges.sort();
+
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+        for &(start, end) in &self.ranges {
+            if let Some(last) = merged.last_mut() {
+                if increment(last.1).is_none_or(|next| start <= next) {
+                    last.1 = last.1.max(end);
+                    continue;
+                }
+            }
+            merged.push((start, end));
+        }
+
+        self.ranges = merged;
+    }
+}
+
+// サロゲート領域を飛ばして次の文字を返す
+pub fn increment(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        c => char::from_u32(c as u32 + 1),
+    }
+}
+
+// サロゲート領域を飛ばして前の文字を返す。c は '\0' より大きいこと
+pub fn decrement(c: char) -> char {
+    match c {
+        '\u{E000}' => '\u{D7FF}',
+        c => char::from_u32(c as u32 - 1).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let set = IntervalSet::new([('x', 'z'), ('a', 'c'), ('b', 'f'), ('g', 'g'), ('z', 'a')]);
+        assert_eq!(set.ranges(), &[('a', 'g'), ('x', 'z')]);
+
+        let set = IntervalSet::new([('\u{D000}', '\u{D7FF}'), ('\u{E000}', '\u{E000}')]);
+        assert_eq!(set.ranges(), &[('\u{D000}', '\u{E000}')]);
+
+        assert!(set.contains('\u{D7FF}'));
+        assert!(!set.contains('a'));
+    }
+
+    #[test]
+    fn test_negate() {
+        let mut set = IntervalSet::new([('b', 'c'), ('x', 'x')]);
+        set.negate();
+        assert_eq!(set.ranges(), &[('\0', 'a'), ('d', 'w'), ('y', char::MAX)]);
+
+        set.negate();
+        assert_eq!(set.ranges(), &[('b', 'c'), ('x', 'x')]);
+
+        let mut set = IntervalSet::new([('\0', '\u{D7FF}'), ('\u{E000}', char::MAX)]);
+        set.negate();
+        assert!(set.ranges().is_empty());
+        set.negate();
+        assert_eq!(set.ranges(), &[('\0', char::MAX)]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = IntervalSet::new([('a', 'm'), ('x', 'z')]);
+        let b = IntervalSet::new([('e', 'f'), ('k', 'y')]);
+
+        let mut set = a.clone();
+        set.intersect(&b);
+        assert_eq!(set.ranges(), &[('e', 'f'), ('k', 'm'), ('x', 'y')]);
+
+        let mut set = a.clone();
+        set.difference(&b);
+        assert_eq!(set.ranges(), &[('a', 'd'), ('g', 'j'), ('z', 'z')]);
+
+        let mut set = a.clone();
+        set.symmetric_difference(&b);
+        assert_eq!(
+            set.ranges(),
+            &[('a', 'd'), ('g', 'j'), ('n', 'w'), ('z', 'z')]
+        );
+
+        let mut set = a.clone();
+        set.intersect(&IntervalSet::default());
+        assert!(set.ranges().is_empty());
+
+        let mut set = a.clone();
+        set.difference(&a);
+        assert!(set.ranges().is_empty());
+    }
+}
@@ -1,39 +1,374 @@
-mod ast;
+pub mod ast;
+mod backtrack;
 mod dfa;
 mod error;
+mod interval;
 mod nfa;
+mod pikevm;
+mod unicode;
 
-use crate::error::Error;
+use std::collections::HashMap;
+use std::ops::{Index, Range};
+use std::sync::Arc;
+
+pub use crate::error::{Error, ErrorKind};
 
 pub struct Regex {
-    dfa: dfa::DFA,
+    nfa: nfa::NFA,
+    executor: Executor,
+    backtrack_limit: usize,
+    capture_names: Vec<Option<String>>,
+    capture_indices: Arc<HashMap<String, usize>>,
 }
 
 impl Regex {
     pub fn new(pattern: &str) -> Result<Regex, Error> {
-        let mut parser = ast::parser::Parser::new(pattern);
+        RegexBuilder::new(pattern).build()
+    }
+
+    fn from_builder(builder: &RegexBuilder) -> Result<Regex, Error> {
+        let mut parser = ast::Parser::with_flags(&builder.pattern, &builder.flags);
         let ast = parser.parse()?;
+        let capture_names = parser.capture_names().to_vec();
 
         let mut nfa = nfa::NFA::new();
-        nfa.build(&ast)?;
+        nfa.set_size_limit(builder.size_limit);
+        nfa.build(&ast, &builder.flags)?;
+
+        let executor = Executor::new(&nfa, builder.engine, builder.dfa_size_limit)?;
 
-        let mut determinizer = dfa::determinize::Determinizer::new(&nfa);
-        determinizer.build()?;
+        let capture_indices = capture_names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| name.clone().map(|name| (name, i)))
+            .collect();
 
         Ok(Regex {
-            dfa: determinizer.dfa,
+            nfa,
+            executor,
+            backtrack_limit: builder.backtrack_limit,
+            capture_names,
+            capture_indices: Arc::new(capture_indices),
         })
     }
 
+    // マッチ全体を表すグループ0を含めたグループの数
+    pub fn captures_len(&self) -> usize {
+        self.capture_names.len()
+    }
+
+    // 各グループの名前をグループ番号順に返す。名前のないグループは None になる
+    pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        self.capture_names.iter().map(|name| name.as_deref())
+    }
+
+    // テキスト中のいずれかの部分文字列にマッチするか
     pub fn is_match(&self, text: &str) -> bool {
-        let mut current_state = self.dfa.start().unwrap();
-        for c in text.chars() {
-            if let Some(state) = self.dfa.next(current_state, c) {
-                current_state = state;
-            } else {
-                return false;
+        self.try_is_match(text)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    // テキスト全体にマッチするか
+    pub fn is_full_match(&self, text: &str) -> bool {
+        self.try_is_full_match(text)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    // 最も左から始まるマッチのうち、パターン中で優先されるものを返す
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.try_find(haystack)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn captures<'h>(&self, haystack: &'h str) -> Option<Captures<'h>> {
+        self.try_captures(haystack)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    // is_match と同じだが、Backtrack エンジンが照合を打ち切った場合に ErrorKind::BacktrackLimitExceeded を返す
+    pub fn try_is_match(&self, text: &str) -> Result<bool, Error> {
+        match &self.executor {
+            Executor::Dfa(dfa) => Ok(dfa.is_match(text)),
+            _ => Ok(self.search(text)?.is_some()),
+        }
+    }
+
+    pub fn try_is_full_match(&self, text: &str) -> Result<bool, Error> {
+        match &self.executor {
+            Executor::Dfa(dfa) => Ok(dfa.is_full_match(text)),
+            Executor::PikeVM => Ok(self.pikevm().is_full_match(text)),
+            Executor::Backtrack(analysis) => self.backtracker(analysis).is_full_match(text),
+        }
+    }
+
+    pub fn try_find<'h>(&self, haystack: &'h str) -> Result<Option<Match<'h>>, Error> {
+        let slots = self.search(haystack)?;
+        Ok(slots.and_then(|slots| Some(Match::new(haystack, slots[0]?, slots[1]?))))
+    }
+
+    pub fn try_captures<'h>(&self, haystack: &'h str) -> Result<Option<Captures<'h>>, Error> {
+        let Some(mut slots) = self.search(haystack)? else {
+            return Ok(None);
+        };
+        slots.resize(self.captures_len() * 2, None);
+
+        Ok(Some(Captures {
+            haystack,
+            slots,
+            names: Arc::clone(&self.capture_indices),
+        }))
+    }
+
+    fn search(&self, haystack: &str) -> Result<Option<pikevm::Slots>, Error> {
+        match &self.executor {
+            // マッチの開始位置をDFAで求めてから、その位置に限ってPike VMで照合する
+            Executor::Dfa(dfa) => Ok(dfa
+                .find_start(haystack)
+                .and_then(|start| self.pikevm().find(haystack, start, true))),
+            Executor::PikeVM => Ok(self.pikevm().find(haystack, 0, false)),
+            Executor::Backtrack(analysis) => self.backtracker(analysis).find(haystack, 0, false),
+        }
+    }
+
+    fn pikevm(&self) -> pikevm::PikeVM<'_> {
+        pikevm::PikeVM::new(&self.nfa)
+    }
+
+    fn backtracker<'a>(&'a self, analysis: &'a backtrack::Analysis) -> backtrack::Backtracker<'a> {
+        backtrack::Backtracker::new(&self.nfa, analysis, self.backtrack_limit)
+    }
+}
+
+// メタ文字をエスケープし、text そのものにマッチするパターンを返す
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if ast::is_meta_character(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// マッチに使うエンジン
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    // パターンが使う機能を実行できるエンジンのうち、最も速いものを選ぶ。
+    // DFAが dfa_size_limit を超える場合は Pike VM を使う
+    #[default]
+    Automatic,
+    // DFA でマッチの有無と開始位置を求め、Pike VM でグループの位置を求める
+    Dfa,
+    PikeVM,
+    // 先読み・後読み、アトミックグループと後方参照を扱える
+    Backtrack,
+}
+
+enum Executor {
+    Dfa(Box<dfa::search::Searcher>),
+    PikeVM,
+    Backtrack(backtrack::Analysis),
+}
+
+impl Executor {
+    fn new(nfa: &nfa::NFA, engine: Engine, dfa_size_limit: usize) -> Result<Self, Error> {
+        let feature = nfa.backtrack_only_feature();
+        match (engine, feature) {
+            (Engine::Automatic, Some(_)) | (Engine::Backtrack, _) => {
+                Ok(Executor::Backtrack(backtrack::Analysis::new(nfa)))
             }
+            (Engine::Dfa | Engine::PikeVM, Some(feature)) => Err(Error::unsupported(&format!(
+                "{} is not supported by the {:?} engine",
+                feature, engine
+            ))),
+            (Engine::PikeVM, None) => Ok(Executor::PikeVM),
+            (Engine::Dfa, None) => Ok(Executor::Dfa(Box::new(dfa::search::Searcher::new(
+                nfa,
+                dfa_size_limit,
+            )?))),
+            // DFAが大きくなりすぎるパターンは Pike VM で照合する
+            (Engine::Automatic, None) => match dfa::search::Searcher::new(nfa, dfa_size_limit) {
+                Ok(searcher) => Ok(Executor::Dfa(Box::new(searcher))),
+                Err(error) if matches!(error.kind(), ErrorKind::DfaSizeLimitExceeded { .. }) => {
+                    Ok(Executor::PikeVM)
+                }
+                Err(error) => Err(error),
+            },
         }
-        self.dfa.accepts().contains(&current_state)
+    }
+}
+
+// パターン全体に適用するフラグを指定して Regex を構築する
+#[derive(Clone, Debug)]
+pub struct RegexBuilder {
+    pattern: String,
+    flags: ast::Flags,
+    size_limit: usize,
+    dfa_size_limit: usize,
+    backtrack_limit: usize,
+    engine: Engine,
+}
+
+impl RegexBuilder {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            flags: ast::Flags::default(),
+            size_limit: nfa::DEFAULT_SIZE_LIMIT,
+            dfa_size_limit: dfa::DEFAULT_SIZE_LIMIT,
+            backtrack_limit: backtrack::DEFAULT_BACKTRACK_LIMIT,
+            engine: Engine::default(),
+        }
+    }
+
+    pub fn build(&self) -> Result<Regex, Error> {
+        Regex::from_builder(self)
+    }
+
+    // 大文字・小文字を区別せずにマッチする。パターン中の (?i) と同じ
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.flags.case_insensitive = Some(yes);
+        self
+    }
+
+    // ^ と $ が行頭・行末にもマッチするようにする。パターン中の (?m) と同じ
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.flags.multi_line = Some(yes);
+        self
+    }
+
+    // 複数行モードの ^ と $ で \r\n を1つの改行として扱う。パターン中の (?R) と同じ
+    pub fn crlf(&mut self, yes: bool) -> &mut Self {
+        self.flags.crlf = Some(yes);
+        self
+    }
+
+    // . が改行にもマッチするようにする。パターン中の (?s) と同じ
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.flags.dot_matches_new_line = Some(yes);
+        self
+    }
+
+    // 空白と # から行末までのコメントを無視する。パターン中の (?x) と同じ
+    pub fn ignore_whitespace(&mut self, yes: bool) -> &mut Self {
+        self.flags.ignore_whitespace = Some(yes);
+        self
+    }
+
+    // 無効にすると \d, \w, \s は ASCII の文字のみを表す。パターン中の (?u) と同じ
+    pub fn unicode(&mut self, yes: bool) -> &mut Self {
+        self.flags.unicode = Some(yes);
+        self
+    }
+
+    // NFAの大きさの上限 (状態の数と、各遷移が持つ文字の範囲の数の合計)。
+    // 超えるパターンは ErrorKind::SizeLimitExceeded で失敗する
+    pub fn size_limit(&mut self, limit: usize) -> &mut Self {
+        self.size_limit = limit;
+        self
+    }
+
+    // Dfa エンジンが構築する各DFAの大きさ(各状態が持つNFAの状態の数と遷移表の要素の数の合計)の上限。超えるパターンは既定では Pike VM で照合し、
+    // Engine::Dfa を指定した場合は ErrorKind::DfaSizeLimitExceeded で失敗する
+    pub fn dfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.dfa_size_limit = limit;
+        self
+    }
+
+    // Backtrack エンジンが1つの開始位置からの照合でたどる状態の数の上限。超えた場合、try_ で始まるメソッドは
+    // ErrorKind::BacktrackLimitExceeded を返し、それ以外のメソッドはパニックする
+    pub fn backtrack_limit(&mut self, limit: usize) -> &mut Self {
+        self.backtrack_limit = limit;
+        self
+    }
+
+    // 使うエンジンを指定する。パターンを実行できない場合は ErrorKind::Unsupported で失敗する
+    pub fn engine(&mut self, engine: Engine) -> &mut Self {
+        self.engine = engine;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h str,
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    fn new(haystack: &'h str, start: usize, end: usize) -> Self {
+        Self {
+            haystack,
+            start,
+            end,
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn as_str(&self) -> &'h str {
+        &self.haystack[self.range()]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Captures<'h> {
+    haystack: &'h str,
+    slots: Vec<Option<usize>>,
+    names: Arc<HashMap<String, usize>>,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl<'h> Captures<'h> {
+    pub fn get(&self, i: usize) -> Option<Match<'h>> {
+        let start = (*self.slots.get(i * 2)?)?;
+        let end = (*self.slots.get(i * 2 + 1)?)?;
+
+        Some(Match::new(self.haystack, start, end))
+    }
+
+    pub fn name(&self, name: &str) -> Option<Match<'h>> {
+        self.get(*self.names.get(name)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match<'h>>> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+}
+
+impl Index<usize> for Captures<'_> {
+    type Output = str;
+
+    fn index(&self, i: usize) -> &str {
+        self.get(i)
+            .map(|m| m.as_str())
+            .unwrap_or_else(|| panic!("no group at index '{}'", i))
+    }
+}
+
+impl Index<&str> for Captures<'_> {
+    type Output = str;
+
+    fn index(&self, name: &str) -> &str {
+        self.name(name)
+            .map(|m| m.as_str())
+            .unwrap_or_else(|| panic!("no group named '{}'", name))
     }
 }
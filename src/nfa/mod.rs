@@ -1,5 +1,11 @@
-use crate::ast::{Ast, Repetition};
+use crate::ast::{
+    Assertion, Ast, AstKind, Capture, Class, ClassItem, ClassSet, ClassSetOpKind, Flags, Group,
+    Lookaround, LookaroundKind, PerlClass, PerlClassKind, PosixClass, PosixClassKind, Repetition,
+    UnicodeClass,
+};
 use crate::error::Error;
+use crate::interval::IntervalSet;
+use crate::unicode;
 pub type StateID = usize;
 
 pub enum State {
@@ -46,7 +52,122 @@ impl Transition {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TransitionKind {
     Literal(char),
+    Ranges(IntervalSet),
     Epsilon,
+    Look(Look),
+    Capture(usize),
+    // start から end までの部分が現在位置の前後で成立するかを調べる。文字は消費しない。
+    // 後読みの部分は現在位置からテキストを後ろへ読む
+    Lookaround {
+        kind: LookaroundKind,
+        start: StateID,
+        end: StateID,
+    },
+    // start から end までの部分で最初に見つかったマッチを消費する。その中へは戻らない
+    Atomic {
+        start: StateID,
+        end: StateID,
+    },
+    // グループ group が記録した文字列と同じ文字列を消費する
+    Backreference {
+        group: usize,
+        case_insensitive: bool,
+    },
+}
+
+impl TransitionKind {
+    // 文字を1つ消費する遷移か
+    pub fn consumes(&self) -> bool {
+        matches!(self, TransitionKind::Literal(_) | TransitionKind::Ranges(_))
+    }
+
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            TransitionKind::Literal(literal) => *literal == c,
+            TransitionKind::Ranges(ranges) => ranges.contains(c),
+            _ => false,
+        }
+    }
+}
+
+// 文字を消費せずに位置についての条件を判定する遷移。
+// 単語境界は unicode が false の場合、ASCII の英数字と '_' のみを単語構成文字とする
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Look {
+    StartText,
+    EndText,
+    // テキスト末尾、または末尾の改行の直前
+    EndTextOptionalNewline,
+    StartLine,
+    EndLine,
+    // \r\n を1つの行末として扱い、\r と \n の間では成立しない
+    StartLineCRLF,
+    EndLineCRLF,
+    WordBoundary { unicode: bool },
+    NotWordBoundary { unicode: bool },
+    WordStart { unicode: bool },
+    WordEnd { unicode: bool },
+}
+
+impl Look {
+    pub fn matches(&self, haystack: &str, at: usize) -> bool {
+        match *self {
+            Look::StartText => at == 0,
+            Look::EndText => at == haystack.len(),
+            Look::EndTextOptionalNewline => {
+                at == haystack.len() || (at + 1 == haystack.len() && haystack.ends_with('\n'))
+            }
+            Look::StartLine => at == 0 || haystack[..at].ends_with('\n'),
+            Look::EndLine => at == haystack.len() || haystack[at..].starts_with('\n'),
+            Look::StartLineCRLF => {
+                let before = haystack[..at].chars().next_back();
+                let after = haystack[at..].chars().next();
+                match before {
+                    None | Some('\n') => true,
+                    Some('\r') => after != Some('\n'),
+                    Some(_) => false,
+                }
+            }
+            Look::EndLineCRLF => {
+                let before = haystack[..at].chars().next_back();
+                let after = haystack[at..].chars().next();
+                match after {
+                    None | Some('\r') => true,
+                    Some('\n') => before != Some('\r'),
+                    Some(_) => false,
+                }
+            }
+            Look::WordBoundary { unicode }
+            | Look::NotWordBoundary { unicode }
+            | Look::WordStart { unicode }
+            | Look::WordEnd { unicode } => {
+                let is_word = |c: Option<char>| c.is_some_and(|c| is_word_char(c, unicode));
+                self.matches_word(
+                    is_word(haystack[..at].chars().next_back()),
+                    is_word(haystack[at..].chars().next()),
+                )
+            }
+        }
+    }
+
+    // 直前と直後の文字が単語構成文字かどうかから、単語境界についての条件を判定する
+    pub fn matches_word(&self, before: bool, after: bool) -> bool {
+        match self {
+            Look::WordBoundary { .. } => before != after,
+            Look::NotWordBoundary { .. } => before == after,
+            Look::WordStart { .. } => !before && after,
+            Look::WordEnd { .. } => before && !after,
+            _ => false,
+        }
+    }
+}
+
+pub fn is_word_char(c: char, unicode: bool) -> bool {
+    if unicode {
+        unicode::is_word_char(c)
+    } else {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
 }
 
 pub struct NFAFragment {
@@ -59,18 +180,43 @@ pub struct NFA {
     start: StateID,
     end: StateID,
     states: Vec<State>,
+    captures_len: usize,
+    flags: Flags,
+    // NFAの大きさの上限。大きさは状態の数と、各遷移が持つ文字の範囲の数の合計とする
+    size_limit: usize,
+    size: usize,
+    // reverse で作られた逆向きのNFAか。アサーションは元のテキストの向きで判定する
+    reversed: bool,
+    // 後読みの中を構築しているか。後読みはテキストを後ろから読む順に連結する
+    backward: bool,
 }
 
+// 構築できるNFAの大きさの上限の既定値
+pub const DEFAULT_SIZE_LIMIT: usize = 1 << 20;
+
 impl NFA {
     pub fn new() -> Self {
         Self {
             start: 0,
             end: 0,
             states: Vec::new(),
+            // グループ0はマッチ全体を表す
+            captures_len: 1,
+            flags: Flags::default(),
+            size_limit: DEFAULT_SIZE_LIMIT,
+            size: 0,
+            reversed: false,
+            backward: false,
         }
     }
 
-    pub fn build(&mut self, ast: &Ast) -> Result<(), Error> {
+    pub fn set_size_limit(&mut self, size_limit: usize) {
+        self.size_limit = size_limit;
+    }
+
+    // flags はパターン全体に適用するフラグの初期値
+    pub fn build(&mut self, ast: &Ast, flags: &Flags) -> Result<(), Error> {
+        self.flags = *flags;
         let states = self.construct(ast)?;
 
         // endをAcceptに変更
@@ -98,6 +244,10 @@ impl NFA {
         self.start
     }
 
+    pub fn end(&self) -> StateID {
+        self.end
+    }
+
     pub fn state(&self, id: StateID) -> Option<&State> {
         self.states.get(id)
     }
@@ -106,10 +256,125 @@ impl NFA {
         self.states.len()
     }
 
+    // グループiの開始位置と終了位置をそれぞれ 2i, 2i+1 番目のスロットに記録する
+    pub fn slots_len(&self) -> usize {
+        self.captures_len * 2
+    }
+
     pub fn is_accept(&self, id: StateID) -> bool {
         matches!(self.states.get(id), Some(State::Accept(_)))
     }
 
+    // NFA 中のアサーションを列挙する。同じものが複数回現れることがある
+    pub fn looks(&self) -> impl Iterator<Item = Look> + '_ {
+        self.states.iter().flat_map(|state| {
+            state
+                .as_transitions()
+                .iter()
+                .filter_map(|transition| match transition.kind {
+                    TransitionKind::Look(look) => Some(look),
+                    _ => None,
+                })
+        })
+    }
+
+    // Backtracker でのみ実行できる機能を使っている場合、その名前を返す
+    pub fn backtrack_only_feature(&self) -> Option<&'static str> {
+        self.transition_kinds().find_map(|kind| match kind {
+            TransitionKind::Lookaround { .. } => Some("lookaround"),
+            TransitionKind::Backreference { .. } => Some("backreferences"),
+            TransitionKind::Atomic { .. } => Some("atomic groups"),
+            _ => None,
+        })
+    }
+
+    // 後方参照が参照するグループの番号を重複なく列挙する
+    pub fn referenced_groups(&self) -> Vec<usize> {
+        let mut groups: Vec<usize> = self
+            .transition_kinds()
+            .filter_map(|kind| match kind {
+                TransitionKind::Backreference { group, .. } => Some(*group),
+                _ => None,
+            })
+            .collect();
+        groups.sort();
+        groups.dedup();
+        groups
+    }
+
+    // 各状態から後方参照に到達しうるか。先読み・後読みとアトミックグループの中もたどる
+    pub fn reaches_backreference(&self) -> Vec<bool> {
+        let mut predecessors = vec![Vec::new(); self.states.len()];
+        let mut stack = Vec::new();
+        for (id, state) in self.states.iter().enumerate() {
+            for transition in state.as_transitions() {
+                predecessors[transition.to_id].push(id);
+                match transition.kind {
+                    TransitionKind::Backreference { .. } => stack.push(id),
+                    TransitionKind::Lookaround { start, .. }
+                    | TransitionKind::Atomic { start, .. } => predecessors[start].push(id),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut reaches = vec![false; self.states.len()];
+        while let Some(id) = stack.pop() {
+            if !reaches[id] {
+                reaches[id] = true;
+                stack.extend(&predecessors[id]);
+            }
+        }
+        reaches
+    }
+
+    // 複数の遷移が入ってくる状態か。異なる経路が合流し、ループが戻ってくるのはこれらの状態に限られる
+    pub fn join_states(&self) -> Vec<bool> {
+        let mut incoming = vec![0; self.states.len()];
+        for state in &self.states {
+            for transition in state.as_transitions() {
+                incoming[transition.to_id] += 1;
+            }
+        }
+        incoming.into_iter().map(|count| count > 1).collect()
+    }
+
+    fn transition_kinds(&self) -> impl Iterator<Item = &TransitionKind> + '_ {
+        self.states
+            .iter()
+            .flat_map(|state| state.as_transitions().iter().map(Transition::kind))
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    // 遷移の向きをすべて反転させ、開始状態と受理状態を入れ替えたNFAを返す
+    pub fn reverse(&self) -> NFA {
+        let mut reversed = NFA::new();
+        reversed.captures_len = self.captures_len;
+        reversed.reversed = !self.reversed;
+        for _ in &self.states {
+            reversed.add_state(State::Transition(Vec::new()));
+        }
+
+        for (from_id, state) in self.states.iter().enumerate() {
+            for transition in state.as_transitions() {
+                reversed.states[transition.to_id]
+                    .as_transitions_mut()
+                    .push(Transition {
+                        to_id: from_id,
+                        kind: transition.kind.clone(),
+                    });
+            }
+        }
+
+        reversed.states[self.start].make_accept();
+        reversed.set_start_end(self.end, self.start);
+
+        reversed
+    }
+
     #[allow(dead_code)]
     pub fn print(&self) {
         println!("NFA:");
@@ -120,13 +385,33 @@ impl NFA {
                 State::Transition(transitions) | State::Accept(transitions) => {
                     println!("State {}: Transitions ->", i);
                     for transition in transitions {
-                        match transition.kind {
+                        match &transition.kind {
                             TransitionKind::Literal(c) => {
                                 println!("  to_id {} on '{}'", transition.to_id, c)
                             }
+                            TransitionKind::Ranges(ranges) => {
+                                println!("  to_id {} on {:?}", transition.to_id, ranges.ranges())
+                            }
                             TransitionKind::Epsilon => {
                                 println!("  to_id {} on 'ε'", transition.to_id)
                             }
+                            TransitionKind::Look(look) => {
+                                println!("  to_id {} on {:?}", transition.to_id, look)
+                            }
+                            TransitionKind::Capture(slot) => {
+                                println!("  to_id {} on slot {}", transition.to_id, slot)
+                            }
+                            TransitionKind::Atomic { start, end } => println!(
+                                "  to_id {} on atomic ({} -> {})",
+                                transition.to_id, start, end
+                            ),
+                            TransitionKind::Backreference { group, .. } => {
+                                println!("  to_id {} on \\{}", transition.to_id, group)
+                            }
+                            TransitionKind::Lookaround { kind, start, end } => println!(
+                                "  to_id {} on {:?} ({} -> {})",
+                                transition.to_id, kind, start, end
+                            ),
                         }
                     }
                 }
@@ -142,16 +427,31 @@ impl NFA {
         to_id: StateID,
         kind: TransitionKind,
     ) -> Result<(), Error> {
+        // 繰り返しの展開では文字クラスの範囲の集合も複製されるため、その大きさも数える
+        if let TransitionKind::Ranges(ranges) = &kind {
+            self.grow(ranges.ranges().len())?;
+        }
         self.states
             .get_mut(from_id)
             .map(|state| state.as_transitions_mut().push(Transition { to_id, kind }))
             .ok_or(Error::state_id_overflow(self.states_count()))
     }
 
-    fn new_fragment(&mut self) -> NFAFragment {
+    fn new_fragment(&mut self) -> Result<NFAFragment, Error> {
+        // 繰り返しの展開で状態数が際限なく増えないよう上限を設ける
+        self.grow(2)?;
+
         let start = self.add_state(State::Transition(Vec::new()));
         let end = self.add_state(State::Transition(Vec::new()));
-        NFAFragment { start, end }
+        Ok(NFAFragment { start, end })
+    }
+
+    fn grow(&mut self, size: usize) -> Result<(), Error> {
+        self.size += size;
+        if self.size > self.size_limit {
+            return Err(Error::size_limit_exceeded(self.size_limit));
+        }
+        Ok(())
     }
 
     fn set_start_end(&mut self, start: StateID, end: StateID) {
@@ -160,34 +460,305 @@ impl NFA {
     }
 
     fn construct(&mut self, ast: &Ast) -> Result<NFAFragment, Error> {
-        match ast {
-            Ast::Literal(c) => self.construct_literal(*c),
-            Ast::Concat(concats) => self.construct_concat(concats),
-            Ast::Alternate(alternates) => self.construct_alternate(alternates),
-            Ast::Repetition(repetition) => self.construct_repetition(repetition),
+        match &ast.kind {
+            AstKind::Empty => self.construct_empty(),
+            AstKind::Literal(c) => self.construct_literal(*c),
+            AstKind::Dot => self.construct_dot(),
+            AstKind::Concat(concats) => self.construct_concat(concats),
+            AstKind::Alternate(alternates) => self.construct_alternate(alternates),
+            AstKind::Repetition(repetition) => self.construct_repetition(repetition),
+            AstKind::Assertion(assertion) => self.construct_assertion(assertion),
+            AstKind::Capture(capture) => self.construct_capture(capture),
+            AstKind::Group(group) => self.construct_group(group),
+            AstKind::Lookaround(lookaround) => self.construct_lookaround(lookaround),
+            AstKind::AtomicGroup(ast) => self.construct_atomic_group(ast),
+            AstKind::Backreference(index) => self.construct_backreference(*index),
+            AstKind::Flags(flags) => self.construct_flags(flags),
+            AstKind::Class(class) => self.construct_class(class),
+            AstKind::PerlClass(class) => self.construct_perl_class(class),
+            AstKind::UnicodeClass(class) => self.construct_unicode_class(class),
         }
     }
 
-    fn construct_literal(&mut self, c: char) -> Result<NFAFragment, Error> {
-        let fragment = self.new_fragment();
+    // グループ内で変更されたフラグはグループを抜けると元に戻る
+    fn construct_scoped(&mut self, flags: &Flags, ast: &Ast) -> Result<NFAFragment, Error> {
+        let saved_flags = self.flags;
+        self.flags.merge(flags);
+
+        let fragment = self.construct(ast);
+
+        self.flags = saved_flags;
+        fragment
+    }
+
+    fn construct_group(&mut self, group: &Group) -> Result<NFAFragment, Error> {
+        self.construct_scoped(&group.flags, &group.ast)
+    }
 
-        self.add_transition(fragment.start, fragment.end, TransitionKind::Literal(c))?;
+    // 内側で変更したフラグは外側に影響しない
+    fn construct_lookaround(&mut self, lookaround: &Lookaround) -> Result<NFAFragment, Error> {
+        let saved_backward = self.backward;
+        self.backward = lookaround.kind.is_behind();
+        let inner = self.construct_scoped(&Flags::default(), &lookaround.ast);
+        self.backward = saved_backward;
+        let inner = inner?;
+
+        let fragment = self.new_fragment()?;
+        self.add_transition(
+            fragment.start,
+            fragment.end,
+            TransitionKind::Lookaround {
+                kind: lookaround.kind,
+                start: inner.start,
+                end: inner.end,
+            },
+        )?;
 
         Ok(fragment)
     }
 
-    fn construct_concat(&mut self, concats: &[Ast]) -> Result<NFAFragment, Error> {
-        let mut concats_iter = concats.iter();
+    fn construct_atomic_group(&mut self, ast: &Ast) -> Result<NFAFragment, Error> {
+        let inner = self.construct_scoped(&Flags::default(), ast)?;
+
+        let fragment = self.new_fragment()?;
+        self.add_transition(
+            fragment.start,
+            fragment.end,
+            TransitionKind::Atomic {
+                start: inner.start,
+                end: inner.end,
+            },
+        )?;
+
+        Ok(fragment)
+    }
+
+    fn construct_backreference(&mut self, index: u32) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+        self.add_transition(
+            fragment.start,
+            fragment.end,
+            TransitionKind::Backreference {
+                group: index as usize,
+                case_insensitive: self.flags.is_case_insensitive(),
+            },
+        )?;
+
+        Ok(fragment)
+    }
+
+    fn construct_empty(&mut self) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Epsilon)?;
+
+        Ok(fragment)
+    }
+
+    fn construct_flags(&mut self, flags: &Flags) -> Result<NFAFragment, Error> {
+        self.flags.merge(flags);
+
+        let fragment = self.new_fragment()?;
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Epsilon)?;
+
+        Ok(fragment)
+    }
 
-        let mut current_fragment = self.construct(
-            concats_iter
-                .next()
-                .ok_or(Error::syntax("Empty concatenation"))?,
+    fn construct_capture(&mut self, capture: &Capture) -> Result<NFAFragment, Error> {
+        let index = capture.index as usize;
+        self.captures_len = self.captures_len.max(index + 1);
+
+        let fragment = self.new_fragment()?;
+        let inner_fragment = self.construct_scoped(&Flags::default(), &capture.ast)?;
+
+        // 後ろから読む場合は終了位置を先に記録する
+        let (first_slot, last_slot) = if self.backward {
+            (index * 2 + 1, index * 2)
+        } else {
+            (index * 2, index * 2 + 1)
+        };
+        self.add_transition(
+            fragment.start,
+            inner_fragment.start,
+            TransitionKind::Capture(first_slot),
         )?;
+        self.add_transition(
+            inner_fragment.end,
+            fragment.end,
+            TransitionKind::Capture(last_slot),
+        )?;
+
+        Ok(fragment)
+    }
+
+    fn construct_assertion(&mut self, assertion: &Assertion) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        let multi_line = self.flags.is_multi_line();
+        let crlf = self.flags.is_crlf();
+        let unicode = self.flags.is_unicode();
+        let look = match assertion {
+            Assertion::StartLine if multi_line && crlf => Look::StartLineCRLF,
+            Assertion::EndLine if multi_line && crlf => Look::EndLineCRLF,
+            Assertion::StartLine if multi_line => Look::StartLine,
+            Assertion::EndLine if multi_line => Look::EndLine,
+            Assertion::StartLine | Assertion::StartText => Look::StartText,
+            Assertion::EndLine | Assertion::EndText => Look::EndText,
+            Assertion::EndTextOptionalNewline => Look::EndTextOptionalNewline,
+            Assertion::WordBoundary => Look::WordBoundary { unicode },
+            Assertion::NotWordBoundary => Look::NotWordBoundary { unicode },
+            Assertion::WordStart => Look::WordStart { unicode },
+            Assertion::WordEnd => Look::WordEnd { unicode },
+        };
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Look(look))?;
+
+        Ok(fragment)
+    }
+
+    fn construct_literal(&mut self, c: char) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        // i フラグが有効な場合は単純ケースフォールディングで同一視される文字も加える
+        let variants = if self.flags.is_case_insensitive() {
+            unicode::simple_fold(c)
+        } else {
+            &[]
+        };
+        for &literal in std::iter::once(&c).chain(variants) {
+            self.add_transition(
+                fragment.start,
+                fragment.end,
+                TransitionKind::Literal(literal),
+            )?;
+        }
+
+        Ok(fragment)
+    }
+
+    fn construct_dot(&mut self) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        // s フラグが無効な場合は改行以外の任意の文字にマッチする。R フラグでは \r も改行とみなす
+        let mut ranges = if self.flags.is_dot_matches_new_line() {
+            IntervalSet::new([])
+        } else if self.flags.is_crlf() {
+            IntervalSet::new([('\n', '\n'), ('\r', '\r')])
+        } else {
+            IntervalSet::new([('\n', '\n')])
+        };
+        ranges.negate();
+
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Ranges(ranges))?;
+
+        Ok(fragment)
+    }
+
+    fn construct_class(&mut self, class: &Class) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        let ranges = self.class_ranges(class)?;
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Ranges(ranges))?;
+
+        Ok(fragment)
+    }
+
+    fn class_ranges(&self, class: &Class) -> Result<IntervalSet, Error> {
+        let mut ranges = self.class_set_ranges(&class.set.value)?;
+        if class.negated {
+            ranges.negate();
+        }
+        Ok(ranges)
+    }
+
+    // 集合演算を評価し、NFA を構築する前に一つの範囲の集合にまとめる
+    fn class_set_ranges(&self, set: &ClassSet) -> Result<IntervalSet, Error> {
+        let items = match set {
+            ClassSet::Union(items) => items,
+            ClassSet::BinaryOp(op) => {
+                let mut ranges = self.class_set_ranges(&op.lhs.value)?;
+                let rhs = self.class_set_ranges(&op.rhs.value)?;
+                match op.kind {
+                    ClassSetOpKind::Intersection => ranges.intersect(&rhs),
+                    ClassSetOpKind::Difference => ranges.difference(&rhs),
+                    ClassSetOpKind::SymmetricDifference => ranges.symmetric_difference(&rhs),
+                }
+                return Ok(ranges);
+            }
+        };
+
+        let literals = IntervalSet::new(items.iter().filter_map(|item| match item.value {
+            ClassItem::Literal(c) => Some((c, c)),
+            ClassItem::Range(start, end) => Some((start, end)),
+            _ => None,
+        }));
+        let mut ranges = self.fold_and_negate(literals, false);
+        for item in items {
+            let item_ranges = match &item.value {
+                ClassItem::Perl(class) => {
+                    let perl = perl_class_ranges(class, self.flags.is_unicode());
+                    self.fold_and_negate(perl, class.negated)
+                }
+                ClassItem::Unicode(class) => {
+                    self.fold_and_negate(unicode_class_ranges(class)?, class.negated)
+                }
+                ClassItem::Posix(class) => {
+                    self.fold_and_negate(posix_class_ranges(class), class.negated)
+                }
+                // 各要素は大文字・小文字について閉じているため、集合演算や否定の後に加え直す必要はない
+                ClassItem::Bracketed(class) => self.class_ranges(class)?,
+                ClassItem::Literal(_) | ClassItem::Range(_, _) => continue,
+            };
+            ranges.union(&item_ranges);
+        }
+
+        Ok(ranges)
+    }
+
+    // i フラグが有効な場合は、否定する前に大文字・小文字の対応する文字を加える
+    fn fold_and_negate(&self, mut ranges: IntervalSet, negated: bool) -> IntervalSet {
+        if self.flags.is_case_insensitive() {
+            unicode::add_case_variants(&mut ranges);
+        }
+        if negated {
+            ranges.negate();
+        }
+        ranges
+    }
+
+    fn construct_perl_class(&mut self, class: &PerlClass) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        let ranges = perl_class_ranges(class, self.flags.is_unicode());
+        let ranges = self.fold_and_negate(ranges, class.negated);
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Ranges(ranges))?;
 
-        for ast in concats_iter {
-            let next_fragment = self.construct(ast)?;
+        Ok(fragment)
+    }
+
+    fn construct_unicode_class(&mut self, class: &UnicodeClass) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        let ranges = self.fold_and_negate(unicode_class_ranges(class)?, class.negated);
+        self.add_transition(fragment.start, fragment.end, TransitionKind::Ranges(ranges))?;
+
+        Ok(fragment)
+    }
 
+    // (?i) などは後に続く要素に影響するため、後ろから読む場合も構築はパターンの順に行う
+    fn construct_concat(&mut self, concats: &[Ast]) -> Result<NFAFragment, Error> {
+        let mut fragments = concats
+            .iter()
+            .map(|ast| self.construct(ast))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.backward {
+            fragments.reverse();
+        }
+
+        let mut fragments_iter = fragments.into_iter();
+        let mut current_fragment = fragments_iter
+            .next()
+            .ok_or(Error::syntax("Empty concatenation"))?;
+
+        for next_fragment in fragments_iter {
             self.add_transition(
                 current_fragment.end,
                 next_fragment.start,
@@ -200,7 +771,7 @@ impl NFA {
     }
 
     fn construct_alternate(&mut self, alternates: &[Ast]) -> Result<NFAFragment, Error> {
-        let fragment = self.new_fragment();
+        let fragment = self.new_fragment()?;
 
         for ast in alternates {
             let alt_fragment = self.construct(ast)?;
@@ -213,60 +784,78 @@ impl NFA {
     }
 
     fn construct_repetition(&mut self, repetition: &Repetition) -> Result<NFAFragment, Error> {
-        match (repetition.min, repetition.max) {
-            (0, Some(1)) => self.construct_zero_or_one(&repetition.ast),
-            (0, None) => self.construct_at_least(&repetition.ast, 0),
-            (1, None) => self.construct_at_least(&repetition.ast, 1),
-            _ => unimplemented!(),
+        let min = repetition.min as usize;
+        match repetition.max {
+            Some(max) => {
+                self.construct_bounded(&repetition.ast, min, max as usize, repetition.greedy)
+            }
+            None => self.construct_at_least(&repetition.ast, min, repetition.greedy),
         }
     }
 
-    fn construct_zero_or_one(&mut self, ast: &Ast) -> Result<NFAFragment, Error> {
-        let fragment = self.new_fragment();
-        let inner_fragment = self.construct(ast)?;
+    // min 回の繰り返しに続けて、省略可能な繰り返しを max - min 回入れ子にする
+    fn construct_bounded(
+        &mut self,
+        ast: &Ast,
+        min: usize,
+        max: usize,
+        greedy: bool,
+    ) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
+
+        let mut end = fragment.start;
+        for _ in 0..min {
+            let inner_fragment = self.construct(ast)?;
+            self.add_transition(end, inner_fragment.start, TransitionKind::Epsilon)?;
+            end = inner_fragment.end;
+        }
 
-        self.add_transition(
-            fragment.start,
-            inner_fragment.start,
-            TransitionKind::Epsilon,
-        )?;
-        self.add_transition(inner_fragment.end, fragment.end, TransitionKind::Epsilon)?;
+        for _ in min..max {
+            let inner_fragment = self.construct(ast)?;
+            // 以降の繰り返しを省略することもできる
+            self.add_branch(end, inner_fragment.start, fragment.end, greedy)?;
+            end = inner_fragment.end;
+        }
 
-        // 空文字を受理するε遷移
-        self.add_transition(fragment.start, fragment.end, TransitionKind::Epsilon)?;
+        self.add_transition(end, fragment.end, TransitionKind::Epsilon)?;
 
         Ok(fragment)
     }
 
-    fn construct_at_least(&mut self, ast: &Ast, n: usize) -> Result<NFAFragment, Error> {
-        let fragment = self.new_fragment();
+    fn construct_at_least(
+        &mut self,
+        ast: &Ast,
+        n: usize,
+        greedy: bool,
+    ) -> Result<NFAFragment, Error> {
+        let fragment = self.new_fragment()?;
 
         // 繰り返し部分
         let mut inner_fragment = self.construct(ast)?;
-
-        self.add_transition(
+        self.add_branch(
             inner_fragment.end,
             inner_fragment.start,
-            TransitionKind::Epsilon,
+            fragment.end,
+            greedy,
         )?;
-        self.add_transition(inner_fragment.end, fragment.end, TransitionKind::Epsilon)?;
 
         if n == 0 {
             // 空文字受理
-            self.add_transition(fragment.start, fragment.end, TransitionKind::Epsilon)?;
-        } else {
-            for _ in 0..n - 1 {
-                let pre_fragment = self.construct(ast)?;
+            self.add_branch(fragment.start, inner_fragment.start, fragment.end, greedy)?;
+            return Ok(fragment);
+        }
 
-                self.add_transition(
-                    pre_fragment.end,
-                    inner_fragment.start,
-                    TransitionKind::Epsilon,
-                )?;
+        for _ in 0..n - 1 {
+            let pre_fragment = self.construct(ast)?;
 
-                // inner_fragment を更新
-                inner_fragment = pre_fragment;
-            }
+            self.add_transition(
+                pre_fragment.end,
+                inner_fragment.start,
+                TransitionKind::Epsilon,
+            )?;
+
+            // inner_fragment を更新
+            inner_fragment = pre_fragment;
         }
 
         // 開始状態から inner_fragment に ε 遷移
@@ -278,4 +867,65 @@ impl NFA {
 
         Ok(fragment)
     }
+
+    // 繰り返しを続ける repeat と抜ける exit へのε遷移を追加する。
+    // 先に追加した遷移ほど優先度が高いため、greedy でなければ exit を優先する
+    fn add_branch(
+        &mut self,
+        from: StateID,
+        repeat: StateID,
+        exit: StateID,
+        greedy: bool,
+    ) -> Result<(), Error> {
+        let (first, second) = if greedy {
+            (repeat, exit)
+        } else {
+            (exit, repeat)
+        };
+
+        self.add_transition(from, first, TransitionKind::Epsilon)?;
+        self.add_transition(from, second, TransitionKind::Epsilon)
+    }
+}
+
+// \d, \w, \s が表す文字の集合 (否定する前のもの)。unicode が false の場合は ASCII の文字に限る
+fn perl_class_ranges(class: &PerlClass, unicode: bool) -> IntervalSet {
+    match (class.kind, unicode) {
+        (PerlClassKind::Digit, true) => unicode::decimal_number(),
+        (PerlClassKind::Digit, false) => IntervalSet::new([('0', '9')]),
+        (PerlClassKind::Word, true) => unicode::word(),
+        (PerlClassKind::Word, false) => {
+            IntervalSet::new([('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')])
+        }
+        (PerlClassKind::Space, true) => unicode::white_space(),
+        (PerlClassKind::Space, false) => IntervalSet::new([('\t', '\r'), (' ', ' ')]),
+    }
+}
+
+// \p{..}, \P{..} が表す文字の集合 (否定する前のもの)
+fn unicode_class_ranges(class: &UnicodeClass) -> Result<IntervalSet, Error> {
+    let table = unicode::property(&class.name, class.value.as_deref())?;
+    Ok(IntervalSet::new(table.iter().copied()))
+}
+
+// [:alpha:] などが表す ASCII の文字の集合 (否定する前のもの)
+fn posix_class_ranges(class: &PosixClass) -> IntervalSet {
+    let ranges: &[(char, char)] = match class.kind {
+        PosixClassKind::Alnum => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+        PosixClassKind::Alpha => &[('A', 'Z'), ('a', 'z')],
+        PosixClassKind::Ascii => &[('\0', '\x7F')],
+        PosixClassKind::Blank => &[('\t', '\t'), (' ', ' ')],
+        PosixClassKind::Cntrl => &[('\0', '\x1F'), ('\x7F', '\x7F')],
+        PosixClassKind::Digit => &[('0', '9')],
+        PosixClassKind::Graph => &[('!', '~')],
+        PosixClassKind::Lower => &[('a', 'z')],
+        PosixClassKind::Print => &[(' ', '~')],
+        PosixClassKind::Punct => &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+        PosixClassKind::Space => &[('\t', '\r'), (' ', ' ')],
+        PosixClassKind::Upper => &[('A', 'Z')],
+        PosixClassKind::Word => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+        PosixClassKind::Xdigit => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+    };
+
+    IntervalSet::new(ranges.iter().copied())
 }
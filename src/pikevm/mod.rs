@@ -0,0 +1,194 @@
+use crate::nfa::{self, TransitionKind, NFA};
+
+pub type Slots = Vec<Option<usize>>;
+
+struct Thread {
+    state: nfa::StateID,
+    slots: Slots,
+}
+
+// 1ステップ分のスレッドを優先度順に保持する
+struct Threads {
+    visited: Vec<bool>,
+    list: Vec<Thread>,
+}
+
+impl Threads {
+    fn new(states_count: usize) -> Self {
+        Self {
+            visited: vec![false; states_count],
+            list: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.visited.iter_mut().for_each(|visited| *visited = false);
+        self.list.clear();
+    }
+}
+
+enum Frame {
+    Explore(nfa::StateID),
+    Capture { to_id: nfa::StateID, slot: usize },
+    RestoreSlot { slot: usize, value: Option<usize> },
+}
+
+// NFAの状態集合を同時にたどり、各スレッドについてグループの位置を記録する
+#[allow(clippy::upper_case_acronyms)]
+pub struct PikeVM<'a> {
+    nfa: &'a NFA,
+}
+
+impl<'a> PikeVM<'a> {
+    pub fn new(nfa: &'a NFA) -> Self {
+        Self { nfa }
+    }
+
+    // start 以降で最も左から始まるマッチのうち、優先度が最も高いもののスロットを返す。
+    // anchored の場合は start から始まるマッチのみを探す
+    pub fn find(&self, haystack: &str, start: usize, anchored: bool) -> Option<Slots> {
+        self.run(haystack, start, anchored, false)
+    }
+
+    // テキスト全体にマッチするか
+    pub fn is_full_match(&self, haystack: &str) -> bool {
+        self.run(haystack, 0, true, true).is_some()
+    }
+
+    // full の場合はテキストの末尾で受理するスレッドのみをマッチとする
+    fn run(&self, haystack: &str, start: usize, anchored: bool, full: bool) -> Option<Slots> {
+        let mut current = Threads::new(self.nfa.states_count());
+        let mut next = Threads::new(self.nfa.states_count());
+        let mut matched: Option<Slots> = None;
+
+        let mut slots = vec![None; self.nfa.slots_len()];
+        let mut at = start;
+        loop {
+            // マッチが見つかるまでは各位置から新たなスレッドを開始する。
+            // 後から開始したスレッドほど優先度は低い
+            if matched.is_none() && (at == start || !anchored) {
+                slots[0] = Some(at);
+                self.add_thread(&mut current, self.nfa.start(), haystack, at, &mut slots);
+            }
+
+            let c = haystack[at..].chars().next();
+            let next_at = at + c.map_or(0, char::len_utf8);
+
+            for thread in current.list.iter_mut() {
+                // 受理したスレッドより優先度の低いスレッドは続ける必要がない
+                if self.nfa.is_accept(thread.state) {
+                    if full && c.is_some() {
+                        continue;
+                    }
+                    thread.slots[1] = Some(at);
+                    matched = Some(thread.slots.clone());
+                    break;
+                }
+                if let Some(c) = c {
+                    self.step(&mut next, thread, c, haystack, next_at);
+                }
+            }
+
+            if c.is_none() || (next.list.is_empty() && (matched.is_some() || anchored)) {
+                break;
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+            at = next_at;
+        }
+
+        matched
+    }
+
+    // スレッドの状態から文字 c を読んで遷移する
+    fn step(
+        &self,
+        threads: &mut Threads,
+        thread: &mut Thread,
+        c: char,
+        haystack: &str,
+        next_at: usize,
+    ) {
+        let transitions = self.nfa.state(thread.state).unwrap().as_transitions();
+        for transition in transitions {
+            if transition.kind().accepts(c) {
+                self.add_thread(
+                    threads,
+                    transition.to_id(),
+                    haystack,
+                    next_at,
+                    &mut thread.slots,
+                );
+            }
+        }
+    }
+
+    // ε遷移をたどって到達できる状態をスレッドとして優先度順に追加する
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        id: nfa::StateID,
+        haystack: &str,
+        at: usize,
+        slots: &mut Slots,
+    ) {
+        let mut stack = vec![Frame::Explore(id)];
+
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Explore(id) => {
+                    if threads.visited[id] {
+                        continue;
+                    }
+                    threads.visited[id] = true;
+
+                    let transitions = self.nfa.state(id).unwrap().as_transitions();
+                    let consumes = transitions
+                        .iter()
+                        .any(|transition| transition.kind().consumes());
+                    if consumes || self.nfa.is_accept(id) {
+                        threads.list.push(Thread {
+                            state: id,
+                            slots: slots.clone(),
+                        });
+                    }
+
+                    // 先に追加した遷移ほど優先度が高いので、逆順にスタックへ積む
+                    for transition in transitions.iter().rev() {
+                        match transition.kind() {
+                            TransitionKind::Epsilon => {
+                                stack.push(Frame::Explore(transition.to_id()))
+                            }
+                            TransitionKind::Look(look) => {
+                                if look.matches(haystack, at) {
+                                    stack.push(Frame::Explore(transition.to_id()));
+                                }
+                            }
+                            TransitionKind::Capture(slot) => stack.push(Frame::Capture {
+                                to_id: transition.to_id(),
+                                slot: *slot,
+                            }),
+                            TransitionKind::Literal(_)
+                            | TransitionKind::Ranges(_)
+                            | TransitionKind::Lookaround { .. }
+                            | TransitionKind::Atomic { .. }
+                            | TransitionKind::Backreference { .. } => {}
+                        }
+                    }
+                }
+                Frame::Capture { to_id, slot } => {
+                    stack.push(Frame::RestoreSlot {
+                        slot,
+                        value: slots[slot],
+                    });
+                    slots[slot] = Some(at);
+                    stack.push(Frame::Explore(to_id));
+                }
+                Frame::RestoreSlot { slot, value } => {
+                    slots[slot] = value;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+mod tables;
+
+use crate::error::Error;
+use crate::interval::IntervalSet;
+
+type Table = &'static [(char, char)];
+
+// \d の Unicode 版 (General_Category=Decimal_Number)
+pub fn decimal_number() -> IntervalSet {
+    IntervalSet::new(tables::perl::DECIMAL_NUMBER.iter().copied())
+}
+
+// \w の Unicode 版
+pub fn word() -> IntervalSet {
+    IntervalSet::new(tables::perl::WORD.iter().copied())
+}
+
+// \w の Unicode 版に含まれるか。\b の判定に使う
+pub fn is_word_char(c: char) -> bool {
+    let table = tables::perl::WORD;
+    let i = table.partition_point(|&(_, end)| end < c);
+    table.get(i).is_some_and(|&(start, _)| start <= c)
+}
+
+// \s の Unicode 版 (White_Space)
+pub fn white_space() -> IntervalSet {
+    IntervalSet::new(tables::perl::WHITE_SPACE.iter().copied())
+}
+
+// \p{name} または \p{name=value} が表す文字の範囲を返す
+pub fn property(name: &str, value: Option<&str>) -> Result<Table, Error> {
+    let Some(value) = value else {
+        // 値を省略した場合は一般カテゴリ、用字、2値プロパティの順に探す
+        let key = normalize(name);
+        return lookup(tables::general_category::BY_NAME, &key)
+            .or_else(|| lookup(tables::script::BY_NAME, &key))
+            .or_else(|| lookup(tables::property_bool::BY_NAME, &key))
+            .ok_or_else(|| Error::unknown_property_name(name));
+    };
+
+    let by_name = match normalize(name).as_str() {
+        "gc" | "generalcategory" => tables::general_category::BY_NAME,
+        "sc" | "script" => tables::script::BY_NAME,
+        _ => return Err(Error::unknown_property_name(name)),
+    };
+    lookup(by_name, &normalize(value)).ok_or_else(|| Error::unknown_property_value(value))
+}
+
+// 単純ケースフォールディングで c と同一視される c 以外の文字
+pub fn simple_fold(c: char) -> &'static [char] {
+    let table = tables::case_folding::CASE_FOLDING_SIMPLE;
+    match table.binary_search_by_key(&c, |&(c, _)| c) {
+        Ok(i) => table[i].1,
+        Err(_) => &[],
+    }
+}
+
+// set の各文字と同一視される文字を set に加える
+pub fn add_case_variants(set: &mut IntervalSet) {
+    let table = tables::case_folding::CASE_FOLDING_SIMPLE;
+    let mut variants = Vec::new();
+    for &(start, end) in set.ranges() {
+        let i = table.partition_point(|&(c, _)| c < start);
+        for &(_, chars) in table[i..].iter().take_while(|&&(c, _)| c <= end) {
+            variants.extend(chars.iter().map(|&c| (c, c)));
+        }
+    }
+    set.union(&IntervalSet::new(variants));
+}
+
+fn lookup(by_name: &[(&str, Table)], key: &str) -> Option<Table> {
+    by_name
+        .binary_search_by_key(&key, |&(name, _)| name)
+        .ok()
+        .map(|i| by_name[i].1)
+}
+
+// 名前の照合では大文字・小文字、空白、'_'、'-' を区別しない (UAX44-LM3)
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,5 @@
+pub mod case_folding;
+pub mod general_category;
+pub mod perl;
+pub mod property_bool;
+pub mod script;
@@ -0,0 +1,88 @@
+use regex::ast::{AstKind, ClassItem, ClassSet, Parser, Position, Span, Spanned, Token};
+
+#[test]
+fn public_ast() {
+    let mut parser = Parser::new("(?<year>\\d{4})\n-x");
+    let ast = parser.parse().unwrap();
+    assert_eq!(
+        ast.span,
+        Span::new(Position::new(0, 1, 1), Position::new(17, 2, 3))
+    );
+
+    let AstKind::Concat(nodes) = &ast.kind else {
+        panic!("{:?}", ast);
+    };
+    let spans: Vec<_> = nodes
+        .iter()
+        .map(|node| (node.span.start.offset, node.span.end.offset))
+        .collect();
+    assert_eq!(spans, [(0, 14), (14, 15), (15, 16), (16, 17)]);
+    assert_eq!(nodes[3].span.start.line, 2);
+    assert_eq!(nodes[3].span.start.column, 2);
+    assert_eq!(parser.capture_names()[1].as_deref(), Some("year"));
+}
+
+#[test]
+fn public_tokens() {
+    let mut parser = Parser::new("[a-c\\d]\n(?x) x #");
+    parser.parse().unwrap();
+    let tokens: Vec<_> = parser
+        .tokens()
+        .iter()
+        .map(|token| (&token.value, token.span.start.offset, token.span.end.offset))
+        .collect();
+    // 文字クラスの中の - は範囲の演算子になり、x フラグの後の空白とコメントは読み飛ばす
+    assert!(matches!(
+        tokens[..],
+        [
+            (Token::OpenBracket, 0, 1),
+            (Token::Character('a'), 1, 2),
+            (Token::RangeOperator, 2, 3),
+            (Token::Character('c'), 3, 4),
+            (Token::PerlClass(_), 4, 6),
+            (Token::CloseBracket, 6, 7),
+            (Token::Character('\n'), 7, 8),
+            (Token::SetFlags(_), 8, 12),
+            (Token::Character('x'), 13, 14),
+        ]
+    ));
+    assert_eq!(
+        parser.tokens()[8].span,
+        Span::new(Position::new(13, 2, 6), Position::new(14, 2, 7))
+    );
+}
+
+#[test]
+fn class_spans() {
+    let ast = Parser::new("x[a-c\\d&&[^b]]").parse().unwrap();
+    let AstKind::Concat(nodes) = &ast.kind else {
+        panic!("{:?}", ast);
+    };
+    let AstKind::Class(class) = &nodes[1].kind else {
+        panic!("{:?}", nodes[1]);
+    };
+    let offsets = |span: Span| (span.start.offset, span.end.offset);
+    assert_eq!(offsets(nodes[1].span), (1, 14));
+    assert_eq!(offsets(class.set.span), (2, 13));
+
+    let ClassSet::BinaryOp(op) = &class.set.value else {
+        panic!("{:?}", class.set);
+    };
+    assert_eq!(offsets(op.lhs.span), (2, 7));
+    assert_eq!(offsets(op.rhs.span), (9, 13));
+
+    let ClassSet::Union(items) = &op.lhs.value else {
+        panic!("{:?}", op.lhs);
+    };
+    let items: Vec<_> = items
+        .iter()
+        .map(|Spanned { value, span }| (value, offsets(*span)))
+        .collect();
+    assert!(matches!(
+        items[..],
+        [
+            (ClassItem::Range('a', 'c'), (2, 5)),
+            (ClassItem::Perl(_), (5, 7))
+        ]
+    ));
+}
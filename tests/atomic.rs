@@ -0,0 +1,51 @@
+use regex::{Engine, ErrorKind, Regex, RegexBuilder};
+
+#[test]
+fn atomic_group() {
+    // アトミックグループの中へは戻らない
+    let re = Regex::new(r"(?>a*)a").unwrap();
+    assert!(!re.is_match("aaa"));
+
+    let re = Regex::new(r"(?>a|ab)c").unwrap();
+    assert!(re.is_match("ac"));
+    assert!(!re.is_match("abc"));
+
+    let re = Regex::new(r"(?>(\w+))\.").unwrap();
+    let caps = re.captures("foo.bar").unwrap();
+    assert_eq!(&caps[0], "foo.");
+    assert_eq!(&caps[1], "foo");
+}
+
+#[test]
+fn possessive_repetition() {
+    let re = Regex::new(r"a*+a").unwrap();
+    assert!(!re.is_match("aaa"));
+
+    let re = Regex::new(r"a++b").unwrap();
+    assert_eq!(re.find("xaab").unwrap().range(), 1..4);
+    assert!(!re.is_match("b"));
+
+    let re = Regex::new(r"a?+a").unwrap();
+    assert!(!re.is_full_match("a"));
+    assert!(re.is_full_match("aa"));
+
+    let re = Regex::new(r"a{1,2}+a").unwrap();
+    assert!(!re.is_full_match("aa"));
+    assert!(re.is_full_match("aaa"));
+
+    let re = Regex::new(r#""[^"]*+""#).unwrap();
+    assert_eq!(re.find(r#"say "hi" now"#).unwrap().as_str(), r#""hi""#);
+    assert!(!re.is_match(r#"say "hi"#));
+}
+
+#[test]
+fn atomic_engine_errors() {
+    let pattern = r"(?>a)b";
+    let build = |engine| RegexBuilder::new(pattern).engine(engine).build();
+
+    for engine in [Engine::Dfa, Engine::PikeVM] {
+        let err = build(engine).err().unwrap();
+        assert!(matches!(err.kind(), ErrorKind::Unsupported(_)), "{:?}", err);
+    }
+    assert!(build(Engine::Backtrack).unwrap().is_match("ab"));
+}
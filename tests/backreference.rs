@@ -0,0 +1,125 @@
+use regex::{Engine, ErrorKind, Regex, RegexBuilder};
+
+#[test]
+fn doubled_words() {
+    let re = Regex::new(r"\b(\w+) \1\b").unwrap();
+    assert_eq!(re.find("this is is a test").unwrap().as_str(), "is is");
+    assert!(!re.is_match("this is a test"));
+    assert!(!re.is_match("is isn't"));
+
+    let re = Regex::new(r"(?<word>\w+) \k<word>").unwrap();
+    let caps = re.captures("say hello hello").unwrap();
+    assert_eq!(&caps["word"], "hello");
+}
+
+#[test]
+fn backreference_full_match() {
+    let re = Regex::new(r"(a+)b\1").unwrap();
+    assert!(re.is_full_match("aabaa"));
+    assert!(!re.is_full_match("aaba"));
+    assert_eq!(re.find("aaaba").unwrap().range(), 2..5);
+
+    let re = Regex::new(r"(['\x22])[^'\x22]*\1").unwrap();
+    assert_eq!(re.find(r#"x = "it" + 'is'"#).unwrap().as_str(), r#""it""#);
+    assert_eq!(re.find(r#"x = "it' + 'is'"#).unwrap().as_str(), "' + '");
+}
+
+#[test]
+fn backreference_case_insensitive() {
+    let re = Regex::new(r"(?i)(\w+) \1").unwrap();
+    assert!(re.is_full_match("Hello HELLO"));
+    // 単純ケースフォールディングでは ß と SS は同一視されない
+    assert!(!re.is_full_match("straße STRASSE"));
+    assert!(re.is_full_match("ΣΑΣ σας"));
+
+    let re = Regex::new(r"(\w+) (?i:\1)").unwrap();
+    assert!(re.is_full_match("ab AB"));
+    let re = Regex::new(r"(\w+) \1").unwrap();
+    assert!(!re.is_full_match("ab AB"));
+}
+
+#[test]
+fn unset_group() {
+    // 参照するグループが何も記録していない場合は失敗する
+    let re = Regex::new(r"(?:(a)|b)\1").unwrap();
+    assert!(re.is_full_match("aa"));
+    assert!(!re.is_match("b"));
+
+    let re = Regex::new(r"(a\1)").unwrap();
+    assert!(!re.is_match("aa"));
+}
+
+#[test]
+fn backreference_errors() {
+    for pattern in [r"\2(a)", r"(a)\2", r"\k<x>", r"\k", r"\kx"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+
+    let err = RegexBuilder::new(r"(a)\1")
+        .engine(Engine::Dfa)
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err.kind(), ErrorKind::Unsupported(_)));
+}
+
+#[test]
+fn backtrack_limit() {
+    let pattern = r"(\w+)\s\1";
+    let haystack = "a".repeat(10_000);
+    let re = RegexBuilder::new(pattern)
+        .backtrack_limit(10_000)
+        .build()
+        .unwrap();
+    let err = re.try_is_match(&haystack).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::BacktrackLimitExceeded { limit: 10_000 }
+    );
+    assert!(re.try_find(&haystack).is_err());
+    assert!(re.try_captures(&haystack).is_err());
+    assert_eq!(re.try_is_match("ab ab"), Ok(true));
+
+    let re = RegexBuilder::new(r"(a*)*\1b")
+        .backtrack_limit(10)
+        .build()
+        .unwrap();
+    assert!(re.try_is_match("aab").is_err());
+    let re = Regex::new(r"(a*)*\1b").unwrap();
+    assert!(re.is_match("aab"));
+    assert!(!re.is_match(&"a".repeat(30)));
+
+    // 既定の上限でも長いテキストで打ち切られずに終わる
+    let re = Regex::new(pattern).unwrap();
+    let haystack = "lorem ipsum dolor sit amet ".repeat(400);
+    assert_eq!(re.try_find(&haystack).map(|m| m.is_some()), Ok(false));
+}
+
+#[test]
+fn backtrack_limit_per_start() {
+    // 上限は開始位置ごとに適用するため、線形なパターンはテキストが長くても打ち切られない
+    let haystack = "lorem ipsum dolor ".repeat(10_000);
+    let re = RegexBuilder::new(r"[a-z]+\d")
+        .engine(Engine::Backtrack)
+        .backtrack_limit(1_000)
+        .build()
+        .unwrap();
+    assert_eq!(re.try_find(&haystack).map(|m| m.is_some()), Ok(false));
+
+    let re = RegexBuilder::new(r"[a-z]+\d")
+        .engine(Engine::Backtrack)
+        .build()
+        .unwrap();
+    let haystack = format!("{}dolor7", haystack);
+    assert_eq!(re.try_find(&haystack).unwrap().unwrap().as_str(), "dolor7");
+}
+
+#[test]
+#[should_panic]
+fn backtrack_limit_panics() {
+    let re = RegexBuilder::new(r"(\w+)\s\1")
+        .backtrack_limit(10_000)
+        .build()
+        .unwrap();
+    re.is_match(&"a".repeat(10_000));
+}
@@ -0,0 +1,104 @@
+use regex::Regex;
+
+#[test]
+fn captures_groups() {
+    let re = Regex::new(r"(a|b)(c+)").unwrap();
+    assert_eq!(re.captures_len(), 3);
+
+    let caps = re.captures("xxbccy").unwrap();
+    assert_eq!(caps.len(), 3);
+    assert_eq!(&caps[0], "bcc");
+    assert_eq!(&caps[1], "b");
+    assert_eq!(&caps[2], "cc");
+    assert_eq!(caps.get(2).unwrap().range(), 3..5);
+    assert!(caps.get(3).is_none());
+
+    assert!(re.captures("abab").is_none());
+}
+
+#[test]
+fn captures_optional_group() {
+    let re = Regex::new(r"a(b)?c|(d)").unwrap();
+
+    let caps = re.captures("ac").unwrap();
+    assert_eq!(&caps[0], "ac");
+    assert!(caps.get(1).is_none());
+    assert!(caps.get(2).is_none());
+
+    let caps = re.captures("xd").unwrap();
+    assert!(caps.get(1).is_none());
+    assert_eq!(caps.get(2).unwrap().range(), 1..2);
+}
+
+#[test]
+fn captures_repeated_group() {
+    let re = Regex::new(r"((a|b)c)+").unwrap();
+
+    let caps = re.captures("acbc").unwrap();
+    assert_eq!(&caps[0], "acbc");
+    assert_eq!(&caps[1], "bc");
+    assert_eq!(&caps[2], "b");
+}
+
+#[test]
+fn captures_iter() {
+    let re = Regex::new(r"(a)(x)?(b)").unwrap();
+    let caps = re.captures("ab").unwrap();
+
+    let groups: Vec<Option<&str>> = caps.iter().map(|m| m.map(|m| m.as_str())).collect();
+    assert_eq!(groups, vec![Some("ab"), Some("a"), None, Some("b")]);
+}
+
+#[test]
+#[should_panic]
+fn captures_index_unmatched() {
+    let re = Regex::new(r"(a)|(b)").unwrap();
+    let caps = re.captures("b").unwrap();
+    let _ = &caps[1];
+}
+
+#[test]
+fn captures_named() {
+    let re = Regex::new(r"(?P<year>(1|2)(0|1)+)-(?<month>(0|1)+)").unwrap();
+
+    let names: Vec<Option<&str>> = re.capture_names().collect();
+    assert_eq!(
+        names,
+        vec![None, Some("year"), None, None, Some("month"), None]
+    );
+
+    let caps = re.captures("on 2010-01").unwrap();
+    assert_eq!(&caps["year"], "2010");
+    assert_eq!(caps.name("month").unwrap().range(), 8..10);
+    assert_eq!(&caps[4], "01");
+    assert!(caps.name("day").is_none());
+}
+
+#[test]
+fn named_group_errors() {
+    use regex::ErrorKind;
+
+    let cases = [
+        (
+            r"(?<a>x)(?<a>y)",
+            ErrorKind::DuplicateGroupName("a".to_string()),
+        ),
+        (
+            r"(?P<a b>x)",
+            ErrorKind::InvalidGroupName("a b".to_string()),
+        ),
+        (r"(?'9'x)", ErrorKind::InvalidGroupName("9".to_string())),
+    ];
+    for (pattern, kind) in cases {
+        let err = Regex::new(pattern).err().unwrap();
+        assert_eq!(err.kind(), &kind, "{}", pattern);
+    }
+
+    // 2つ目のグループの位置を指す
+    let err = Regex::new(r"(?<a>x)(?<a>y)").err().unwrap();
+    assert_eq!(err.position(), Some(7));
+
+    for pattern in [r"(?P<a", r"(?%)"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
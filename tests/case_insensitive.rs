@@ -0,0 +1,71 @@
+use regex::{Regex, RegexBuilder};
+
+#[test]
+fn builder_case_insensitive() {
+    let re = RegexBuilder::new("abc")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_full_match("AbC"));
+
+    // パターン中のフラグが優先される
+    let re = RegexBuilder::new("a(?-i)b")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_full_match("Ab"));
+    assert!(!re.is_full_match("AB"));
+}
+
+#[test]
+fn simple_case_folding() {
+    // K, k, KELVIN SIGN は同一視される
+    let re = Regex::new(r"(?i)k").unwrap();
+    for s in ["k", "K", "\u{212A}"] {
+        assert!(re.is_full_match(s), "{}", s);
+    }
+    let re = Regex::new("(?i)\u{212A}").unwrap();
+    assert!(re.is_full_match("k"));
+
+    let re = Regex::new(r"(?i)σ").unwrap();
+    for s in ["σ", "ς", "Σ"] {
+        assert!(re.is_full_match(s), "{}", s);
+    }
+
+    let re = Regex::new(r"(?i)ß").unwrap();
+    assert!(re.is_full_match("\u{1E9E}"));
+    // 複数の文字への対応 (ß と ss) は扱わない
+    assert!(!re.is_full_match("ss"));
+}
+
+#[test]
+fn case_insensitive_class() {
+    let re = Regex::new(r"(?i)[k-l]+").unwrap();
+    assert!(re.is_full_match("kLK\u{212A}"));
+
+    let re = Regex::new(r"(?i)[^k]").unwrap();
+    assert!(!re.is_match("\u{212A}"));
+    assert!(re.is_match("j"));
+
+    let re = Regex::new(r"(?i)\p{Lu}+").unwrap();
+    assert!(re.is_full_match("aBc"));
+
+    let re = Regex::new(r"(?i)[[:upper:]]+").unwrap();
+    assert!(re.is_full_match("aBc"));
+
+    let re = Regex::new(r"(?i)[a-z--[k]]+").unwrap();
+    assert!(re.is_full_match("abJ"));
+    assert!(!re.is_match("K"));
+    assert!(!re.is_match("\u{212A}"));
+}
+
+#[test]
+fn case_insensitive_negated_class() {
+    // 否定する前に大文字・小文字を同一視する
+    let re = Regex::new(r"(?i)\P{Lu}").unwrap();
+    assert!(!re.is_match("aB"));
+    assert!(re.is_match("1"));
+
+    let re = Regex::new(r"(?i)[^[:lower:]]").unwrap();
+    assert!(!re.is_match("aB"));
+}
@@ -0,0 +1,75 @@
+use regex::Regex;
+
+#[test]
+fn class_ranges() {
+    let re = Regex::new(r"[0-9]+").unwrap();
+    assert_eq!(re.find("abc 2024 x").unwrap().as_str(), "2024");
+    assert!(!re.is_match("abc"));
+
+    let re = Regex::new(r"[a-cx-z_]+").unwrap();
+    assert!(re.is_full_match("abz_x"));
+    assert!(!re.is_full_match("abd"));
+
+    let re = Regex::new(r"[ぁ-ん]+").unwrap();
+    assert_eq!(
+        re.find("カタカナとひらがな").unwrap().as_str(),
+        "とひらがな"
+    );
+}
+
+#[test]
+fn class_negated() {
+    let re = Regex::new(r"[^0-9]+").unwrap();
+    assert_eq!(re.find("123abc😀456").unwrap().as_str(), "abc😀");
+    assert!(!re.is_match("0123"));
+
+    let re = Regex::new(r"a[^b]c").unwrap();
+    assert!(re.is_full_match("a\nc"));
+    assert!(!re.is_full_match("abc"));
+}
+
+#[test]
+fn class_edge_cases() {
+    let re = Regex::new(r"[]a]+").unwrap();
+    assert!(re.is_full_match("a]a"));
+
+    let re = Regex::new(r"[^]]").unwrap();
+    assert!(!re.is_full_match("]"));
+    assert!(re.is_full_match("a"));
+
+    let re = Regex::new(r"[-a][a-]").unwrap();
+    assert!(re.is_full_match("--"));
+    assert!(re.is_full_match("a-"));
+
+    let re = Regex::new(r"[\]\-\\*+?(|)^$.]+").unwrap();
+    assert!(re.is_full_match(r"]-\*+?(|)^$."));
+    assert!(!re.is_full_match("a"));
+
+    let re = Regex::new(r"[\x41-\x43\t]+").unwrap();
+    assert!(re.is_full_match("AB\tC"));
+}
+
+#[test]
+fn class_captures() {
+    let re = Regex::new(r"([a-z]+)@([a-z]+)").unwrap();
+    let caps = re.captures("mail: user@example").unwrap();
+    assert_eq!(&caps[1], "user");
+    assert_eq!(&caps[2], "example");
+}
+
+#[test]
+fn class_case_insensitive() {
+    let re = Regex::new(r"(?i)[a-c]+").unwrap();
+    assert!(re.is_full_match("aBC"));
+
+    let re = Regex::new(r"(?i)[^a]").unwrap();
+    assert!(!re.is_full_match("A"));
+    assert!(re.is_full_match("b"));
+}
+
+#[test]
+fn class_errors() {
+    for pattern in [r"[a", r"[z-a]", r"[]", r"[^"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
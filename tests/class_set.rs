@@ -0,0 +1,79 @@
+use regex::Regex;
+
+#[test]
+fn class_difference() {
+    let re = Regex::new(r"[a-z--[aeiou]]+").unwrap();
+    assert_eq!(re.find("aebcdi").unwrap().as_str(), "bcd");
+    assert!(!re.is_match("aeiou"));
+
+    // 入れ子を使わず文字を並べてもよい
+    let re = Regex::new(r"^[0-9--4-6]+$").unwrap();
+    assert!(re.is_match("01239"));
+    assert!(!re.is_match("0145"));
+}
+
+#[test]
+fn class_intersection() {
+    let re = Regex::new(r"^[\p{L}&&\p{Greek}]+$").unwrap();
+    assert!(re.is_match("αβγ"));
+    assert!(!re.is_match("abc"));
+    // ギリシャ文字の記号は文字ではない
+    assert!(!re.is_match("\u{0375}"));
+
+    let re = Regex::new(r"^[\w&&[^\d]]+$").unwrap();
+    assert!(re.is_match("abc_"));
+    assert!(!re.is_match("a1"));
+}
+
+#[test]
+fn class_symmetric_difference() {
+    let re = Regex::new(r"^[a-f~~d-h]+$").unwrap();
+    assert!(re.is_match("abcgh"));
+    assert!(!re.is_match("d"));
+    assert!(!re.is_match("f"));
+}
+
+#[test]
+fn class_set_operators_are_left_associative() {
+    // ([a-z] -- [a-m]) && [k-q] は [n-q]
+    let re = Regex::new(r"^[a-z--a-m&&k-q]+$").unwrap();
+    assert!(re.is_match("nopq"));
+    assert!(!re.is_match("k"));
+    assert!(!re.is_match("r"));
+}
+
+#[test]
+fn nested_class() {
+    let re = Regex::new(r"^[a[0-9][^\w]]+$").unwrap();
+    assert!(re.is_match("a0 !"));
+    assert!(!re.is_match("b"));
+
+    let re = Regex::new(r"^[^[a-c]x]+$").unwrap();
+    assert!(re.is_match("dyz"));
+    assert!(!re.is_match("b"));
+    assert!(!re.is_match("x"));
+
+    let re = Regex::new(r"^[[:alpha:]--[:upper:]]+$").unwrap();
+    assert!(re.is_match("abc"));
+    assert!(!re.is_match("aBc"));
+}
+
+#[test]
+fn class_set_with_negation() {
+    let re = Regex::new(r"^[^a-z&&[^aeiou]]+$").unwrap();
+    assert!(re.is_match("aeiou1"));
+    assert!(!re.is_match("b"));
+}
+
+#[test]
+fn single_operator_characters_are_literals() {
+    let re = Regex::new(r"^[&~-]+$").unwrap();
+    assert!(re.is_match("&~-"));
+}
+
+#[test]
+fn class_set_errors() {
+    for pattern in [r"[a&&b", r"[a[b]", r"[[a]"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
@@ -0,0 +1,48 @@
+use regex::{Regex, RegexBuilder};
+
+#[test]
+fn dot() {
+    let re = Regex::new(r"a.c").unwrap();
+    assert!(re.is_full_match("abc"));
+    assert!(re.is_full_match("a😀c"));
+    assert!(re.is_full_match("a.c"));
+    assert!(!re.is_full_match("a\nc"));
+    assert!(!re.is_full_match("ac"));
+
+    let re = Regex::new(r"a\.c").unwrap();
+    assert!(re.is_full_match("a.c"));
+    assert!(!re.is_full_match("abc"));
+}
+
+#[test]
+fn dot_find() {
+    let re = Regex::new(r"<.+>").unwrap();
+    assert_eq!(re.find("x <a><b>\n<c>").unwrap().as_str(), "<a><b>");
+
+    let re = Regex::new(r"(.*)=(.*)").unwrap();
+    let caps = re.captures("key=value").unwrap();
+    assert_eq!(&caps[1], "key");
+    assert_eq!(&caps[2], "value");
+}
+
+#[test]
+fn dot_matches_new_line() {
+    let re = Regex::new(r"(?s)a.c").unwrap();
+    assert!(re.is_full_match("a\nc"));
+
+    let re = Regex::new(r"(?s:.)\n.").unwrap();
+    assert!(re.is_full_match("\n\na"));
+    assert!(!re.is_full_match("\n\n\n"));
+
+    let re = RegexBuilder::new(r"a.c")
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+    assert!(re.is_full_match("a\nc"));
+
+    let re = RegexBuilder::new(r"a(?-s).c")
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+    assert!(!re.is_full_match("a\nc"));
+}
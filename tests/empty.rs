@@ -0,0 +1,41 @@
+use regex::Regex;
+
+#[test]
+fn empty_pattern() {
+    let re = Regex::new("").unwrap();
+    assert!(re.is_match(""));
+    assert!(re.is_match("abc"));
+    assert!(re.is_full_match(""));
+    assert!(!re.is_full_match("a"));
+    assert_eq!(re.find("abc").unwrap().range(), 0..0);
+}
+
+#[test]
+fn empty_alternative() {
+    let re = Regex::new("a|").unwrap();
+    assert!(re.is_full_match("a"));
+    assert!(re.is_full_match(""));
+    assert_eq!(re.find("ba").unwrap().range(), 0..0);
+    assert_eq!(re.find("ab").unwrap().range(), 0..1);
+
+    let re = Regex::new("(|b)c").unwrap();
+    let caps = re.captures("bc").unwrap();
+    assert_eq!(&caps[0], "bc");
+    assert_eq!(&caps[1], "b");
+    let caps = re.captures("c").unwrap();
+    assert_eq!(caps.get(1).unwrap().range(), 0..0);
+
+    let re = Regex::new("a||b").unwrap();
+    assert!(re.is_full_match(""));
+    assert!(re.is_full_match("b"));
+}
+
+#[test]
+fn empty_group() {
+    let re = Regex::new("a()b").unwrap();
+    let caps = re.captures("xab").unwrap();
+    assert_eq!(caps.get(1).unwrap().range(), 2..2);
+
+    let re = Regex::new("(?:)*a(|)+").unwrap();
+    assert!(re.is_full_match("a"));
+}
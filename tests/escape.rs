@@ -0,0 +1,70 @@
+use regex::{ErrorKind, Regex};
+
+#[test]
+fn escape_metacharacters() {
+    let re = Regex::new(r"a\*b\+\(c\|d\)\?").unwrap();
+    assert!(re.is_full_match("a*b+(c|d)?"));
+    assert!(!re.is_full_match("aab"));
+
+    let re = Regex::new(r"\^\$\\").unwrap();
+    assert_eq!(re.find(r"x^$\y").unwrap().range(), 1..4);
+}
+
+#[test]
+fn escape_code_points() {
+    let re = Regex::new(r"\x41\x{3042}\u{1F600}é").unwrap();
+    assert!(re.is_full_match("Aあ😀é"));
+
+    let re = Regex::new(r"a\tb\n").unwrap();
+    assert!(re.is_full_match("a\tb\n"));
+
+    // 8進数のエスケープ
+    for (pattern, text) in [(r"\0\012", "\0\n"), (r"\o{101}+", "AA")] {
+        let re = Regex::new(pattern).unwrap();
+        assert!(re.is_full_match(text), "{}", pattern);
+    }
+}
+
+#[test]
+fn escape_function() {
+    let text = r"1+1=2? (a|b) [x-y] {3} ^$ \ . * # & ~ a&&b";
+    let escaped = regex::escape(text);
+    assert_eq!(
+        escaped,
+        r"1\+1=2\? \(a\|b\) \[x\-y\] \{3\} \^\$ \\ \. \* \# \& \~ a\&\&b"
+    );
+
+    let re = Regex::new(&escaped).unwrap();
+    assert!(re.is_full_match(text));
+
+    // 文字クラスの中でも文字として扱われる
+    let re = Regex::new(&format!("[{}]+", regex::escape("a-z&&~"))).unwrap();
+    assert!(re.is_full_match("-a&z~"));
+    assert!(!re.is_match("b"));
+
+    assert_eq!(regex::escape("日本語"), "日本語");
+}
+
+#[test]
+fn quoted_literal() {
+    let re = Regex::new(r"\Q(a+b)*\E+c").unwrap();
+    assert!(re.is_full_match("(a+b)**c"));
+    assert!(!re.is_match("aabc"));
+
+    // \E がなければパターンの終わりまで続く
+    let re = Regex::new(r"x\Q[.*").unwrap();
+    assert!(re.is_full_match("x[.*"));
+
+    let re = Regex::new(r"[\Q]-\E]+").unwrap();
+    assert!(re.is_full_match("]-]"));
+    assert!(!re.is_match("a"));
+}
+
+#[test]
+fn escape_errors() {
+    for (pattern, position) in [(r"ab\", 2), (r"a\k", 1), (r"(a\x{41)", 2), (r"\u{D800}", 0)] {
+        let error = Regex::new(pattern).err().unwrap();
+        assert!(matches!(error.kind(), ErrorKind::SyntaxError(_)));
+        assert_eq!(error.position(), Some(position), "{}", pattern);
+    }
+}
@@ -0,0 +1,89 @@
+use regex::{Engine, Regex, RegexBuilder};
+
+#[test]
+fn find_substring() {
+    let re = Regex::new(r"ab").unwrap();
+
+    let m = re.find("xaby").unwrap();
+    assert_eq!(m.start(), 1);
+    assert_eq!(m.end(), 3);
+    assert_eq!(m.as_str(), "ab");
+
+    assert!(re.find("xy").is_none());
+    assert!(re.find("").is_none());
+}
+
+#[test]
+fn find_leftmost() {
+    let re = Regex::new(r"ab|bcde").unwrap();
+    let m = re.find("abcde").unwrap();
+    assert_eq!(m.range(), 0..2);
+
+    let re = Regex::new(r"abcd|c").unwrap();
+    let m = re.find("xabcd").unwrap();
+    assert_eq!(m.range(), 1..5);
+
+    let re = Regex::new(r"a(b|c)+d").unwrap();
+    let m = re.find("aad abcbd acd").unwrap();
+    assert_eq!(m.as_str(), "abcbd");
+}
+
+#[test]
+fn find_longest() {
+    let re = Regex::new(r"a+").unwrap();
+    let m = re.find("baaab").unwrap();
+    assert_eq!(m.range(), 1..4);
+
+    let re = Regex::new(r"(a|b)*").unwrap();
+    let m = re.find("cab").unwrap();
+    assert_eq!(m.range(), 0..0);
+}
+
+#[test]
+fn find_multibyte() {
+    let re = Regex::new(r"ほげ+").unwrap();
+    let m = re.find("あいほげげう").unwrap();
+    assert_eq!(m.start(), 6);
+    assert_eq!(m.end(), 15);
+    assert_eq!(m.as_str(), "ほげげ");
+}
+
+#[test]
+fn find_anchors() {
+    let re = Regex::new(r"^ab").unwrap();
+    assert_eq!(re.find("abab").unwrap().range(), 0..2);
+    assert!(re.find("cab").is_none());
+
+    let re = Regex::new(r"ab$").unwrap();
+    assert_eq!(re.find("abab").unwrap().range(), 2..4);
+    assert!(re.find("abc").is_none());
+
+    let re = Regex::new(r"b*$").unwrap();
+    assert_eq!(re.find("abb").unwrap().range(), 1..3);
+    assert_eq!(re.find("abc").unwrap().range(), 3..3);
+
+    let re = Regex::new(r"^a*").unwrap();
+    assert_eq!(re.find("baa").unwrap().range(), 0..0);
+}
+
+#[test]
+fn find_early_match_in_large_haystack() {
+    let mut haystack = String::from("INFO start\nERROR disk full id=42\n");
+    while haystack.len() < 1_000_000 {
+        haystack.push_str("ERROR request failed id=7\n");
+    }
+
+    // 最も左のマッチが終わった時点で走査を止め、後ろのマッチは見ない
+    let re = RegexBuilder::new(r"ERROR [\w ]+ id=(\d+)")
+        .engine(Engine::Dfa)
+        .build()
+        .unwrap();
+    assert_eq!(re.find(&haystack).unwrap().range(), 11..32);
+    assert_eq!(&re.captures(&haystack).unwrap()[1], "42");
+
+    let re = RegexBuilder::new(r"(?m)^\w+$|full")
+        .engine(Engine::Dfa)
+        .build()
+        .unwrap();
+    assert_eq!(re.find(&haystack).unwrap().as_str(), "full");
+}
@@ -0,0 +1,114 @@
+use regex::{Regex, RegexBuilder};
+
+#[test]
+fn non_capturing_group() {
+    let re = Regex::new(r"(?:ab)+(c)").unwrap();
+    assert_eq!(re.captures_len(), 2);
+
+    let caps = re.captures("xababc").unwrap();
+    assert_eq!(&caps[0], "ababc");
+    assert_eq!(&caps[1], "c");
+}
+
+#[test]
+fn case_insensitive() {
+    let re = Regex::new(r"(?i)abc").unwrap();
+    assert!(re.is_full_match("ABC"));
+    assert!(re.is_full_match("aBc"));
+    assert!(!re.is_full_match("abd"));
+
+    let re = Regex::new(r"a(?i)b|c").unwrap();
+    assert!(re.is_full_match("aB"));
+    assert!(re.is_full_match("C"));
+    assert!(!re.is_full_match("Ab"));
+
+    let re = Regex::new(r"(?i:a)b").unwrap();
+    assert!(re.is_full_match("Ab"));
+    assert!(!re.is_full_match("AB"));
+
+    let re = Regex::new(r"(?i)a(?-i)b").unwrap();
+    assert!(re.is_full_match("Ab"));
+    assert!(!re.is_full_match("aB"));
+
+    let re = Regex::new(r"((?i)a)b").unwrap();
+    assert!(re.is_full_match("Ab"));
+    assert!(!re.is_full_match("AB"));
+
+    let re = Regex::new(r"(?i)ΣΑ").unwrap();
+    assert!(re.is_full_match("σα"));
+}
+
+#[test]
+fn multi_line() {
+    let re = Regex::new(r"(?m)^b$").unwrap();
+    assert!(re.is_match("a\nb\nc"));
+    assert!(!re.is_match("a\nbc"));
+    assert_eq!(re.find("a\nb\nc").unwrap().range(), 2..3);
+
+    let re = Regex::new(r"^b$").unwrap();
+    assert!(!re.is_match("a\nb\nc"));
+
+    let re = Regex::new(r"(?m)(b|c)$").unwrap();
+    let caps = re.captures("ab\ncd").unwrap();
+    assert_eq!(caps.get(0).unwrap().range(), 1..2);
+    assert_eq!(&caps[1], "b");
+
+    let re = Regex::new(r"(?m:^a)$").unwrap();
+    assert!(re.is_full_match("a"));
+    assert!(!re.is_match("a\n"));
+    assert!(re.is_match("b\na"));
+}
+
+#[test]
+fn ignore_whitespace() {
+    let re = Regex::new("(?x) a b # comment\n c").unwrap();
+    assert!(re.is_full_match("abc"));
+
+    let re = Regex::new("(?x: a b ) c").unwrap();
+    assert!(re.is_full_match("ab c"));
+    assert!(!re.is_full_match("abc"));
+
+    // エスケープした空白と文字クラスの中の空白は文字として扱う
+    let re = Regex::new("(?x) a\\ b [ ] c").unwrap();
+    assert!(re.is_full_match("a b c"));
+    assert!(!re.is_full_match("abc"));
+
+    // 繰り返し回数の中の空白も読み飛ばす
+    let re = Regex::new("(?x) a{2, 3} b{ 2 }").unwrap();
+    assert!(re.is_full_match("aabb"));
+    assert!(re.is_full_match("aaabb"));
+    assert!(!re.is_full_match("aaaabb"));
+}
+
+#[test]
+fn builder_ignore_whitespace() {
+    let pattern = r"
+        (?<year>\d{4})  # 年
+        -
+        (?<month>\d{2}) # 月
+    ";
+    let re = RegexBuilder::new(pattern)
+        .ignore_whitespace(true)
+        .build()
+        .unwrap();
+    let caps = re.captures("on 2024-05").unwrap();
+    assert_eq!(&caps["year"], "2024");
+    assert_eq!(&caps["month"], "05");
+
+    // パターン中のフラグで無効にできる
+    let re = RegexBuilder::new("a (?-x:b c)")
+        .ignore_whitespace(true)
+        .build()
+        .unwrap();
+    assert!(re.is_full_match("ab c"));
+
+    let re = RegexBuilder::new("a b").build().unwrap();
+    assert!(!re.is_match("ab"));
+}
+
+#[test]
+fn flag_errors() {
+    for pattern in [r"(?z)", r"(?)", r"(?i", r"(?i-)", r"(?i)*"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
@@ -5,66 +5,106 @@ use regex::Regex;
 fn case0() {
     let re = Regex::new(r"(a|b)*").unwrap();
 
-    assert!(re.is_match("aa"));
-    assert!(re.is_match(""));
-    assert!(re.is_match("bbb"));
-    assert!(re.is_match("ab"));
-    assert!(!re.is_match("abc"));
+    assert!(re.is_full_match("aa"));
+    assert!(re.is_full_match(""));
+    assert!(re.is_full_match("bbb"));
+    assert!(re.is_full_match("ab"));
+    assert!(!re.is_full_match("abc"));
 }
 
 #[test]
 fn case1() {
     let re = Regex::new(r"ab|cd").unwrap();
 
-    assert!(re.is_match("ab"));
-    assert!(re.is_match("cd"));
-    assert!(!re.is_match("ac"));
-    assert!(!re.is_match("bd"));
+    assert!(re.is_full_match("ab"));
+    assert!(re.is_full_match("cd"));
+    assert!(!re.is_full_match("ac"));
+    assert!(!re.is_full_match("bd"));
 }
 
 #[test]
 fn case2() {
     let re = Regex::new(r"a(b|c)d").unwrap();
 
-    assert!(re.is_match("abd"));
-    assert!(re.is_match("acd"));
-    assert!(!re.is_match("ad"));
-    assert!(!re.is_match("abcd"));
+    assert!(re.is_full_match("abd"));
+    assert!(re.is_full_match("acd"));
+    assert!(!re.is_full_match("ad"));
+    assert!(!re.is_full_match("abcd"));
 }
 
 #[test]
 fn case3() {
     let re = Regex::new(r"a(b|c)*d").unwrap();
 
-    assert!(re.is_match("ad"));
-    assert!(re.is_match("abd"));
-    assert!(re.is_match("acd"));
-    assert!(re.is_match("abcbcd"));
-    assert!(!re.is_match("abc"));
-    assert!(!re.is_match("aabcd"));
+    assert!(re.is_full_match("ad"));
+    assert!(re.is_full_match("abd"));
+    assert!(re.is_full_match("acd"));
+    assert!(re.is_full_match("abcbcd"));
+    assert!(!re.is_full_match("abc"));
+    assert!(!re.is_full_match("aabcd"));
 }
 
 #[test]
 fn case4() {
     let re = Regex::new(r"a(b|c)?d").unwrap();
 
-    assert!(re.is_match("ad"));
-    assert!(re.is_match("abd"));
-    assert!(re.is_match("acd"));
-    assert!(!re.is_match("abcbcd"));
-    assert!(!re.is_match("abc"));
-    assert!(!re.is_match("abcd"));
+    assert!(re.is_full_match("ad"));
+    assert!(re.is_full_match("abd"));
+    assert!(re.is_full_match("acd"));
+    assert!(!re.is_full_match("abcbcd"));
+    assert!(!re.is_full_match("abc"));
+    assert!(!re.is_full_match("abcd"));
 }
 
 #[test]
 fn case5() {
     let re = Regex::new(r"a(b|c)+d").unwrap();
 
-    assert!(re.is_match("abd"));
-    assert!(re.is_match("acd"));
-    assert!(re.is_match("abcd"));
-    assert!(re.is_match("abcbcd"));
-    assert!(!re.is_match("ad"));
+    assert!(re.is_full_match("abd"));
+    assert!(re.is_full_match("acd"));
+    assert!(re.is_full_match("abcd"));
+    assert!(re.is_full_match("abcbcd"));
+    assert!(!re.is_full_match("ad"));
+    assert!(!re.is_full_match("abc"));
+    assert!(!re.is_full_match("babcbd"));
+}
+
+#[test]
+fn unanchored() {
+    let re = Regex::new(r"ab").unwrap();
+
+    assert!(re.is_match("ab"));
+    assert!(re.is_match("xaby"));
+    assert!(!re.is_match("ba"));
+    assert!(!re.is_match(""));
+    assert!(!re.is_full_match("xaby"));
+
+    let re = Regex::new(r"(a|b)*").unwrap();
+    assert!(re.is_match(""));
+    assert!(re.is_match("abc"));
+}
+
+#[test]
+fn anchors() {
+    let re = Regex::new(r"^ab").unwrap();
+    assert!(re.is_match("abc"));
+    assert!(!re.is_match("cab"));
+
+    let re = Regex::new(r"ab$").unwrap();
+    assert!(re.is_match("cab"));
     assert!(!re.is_match("abc"));
-    assert!(!re.is_match("babcbd"));
+
+    let re = Regex::new(r"^(a|b)*$").unwrap();
+    assert!(re.is_match(""));
+    assert!(re.is_match("abba"));
+    assert!(!re.is_match("abca"));
+
+    let re = Regex::new(r"a^b").unwrap();
+    assert!(!re.is_match("ab"));
+    assert!(!re.is_full_match("ab"));
+
+    let re = Regex::new(r"a$|b").unwrap();
+    assert!(re.is_full_match("a"));
+    assert!(re.is_full_match("b"));
+    assert!(!re.is_match("ac"));
 }
@@ -0,0 +1,62 @@
+use regex::Regex;
+
+#[test]
+fn lazy_quantifiers() {
+    let re = Regex::new(r"a+?").unwrap();
+    assert_eq!(re.find("baaa").unwrap().range(), 1..2);
+
+    let re = Regex::new(r"<.*?>").unwrap();
+    assert_eq!(re.find("x<a><b>").unwrap().as_str(), "<a>");
+
+    let re = Regex::new(r"a??b").unwrap();
+    assert_eq!(re.find("aab").unwrap().as_str(), "ab");
+
+    let re = Regex::new(r"a{2,4}?").unwrap();
+    assert_eq!(re.find("aaaa").unwrap().as_str(), "aa");
+
+    let re = Regex::new(r"a{2,}?").unwrap();
+    assert_eq!(re.find("aaaa").unwrap().as_str(), "aa");
+
+    let re = Regex::new(r"a*?").unwrap();
+    assert_eq!(re.find("aaa").unwrap().range(), 0..0);
+}
+
+#[test]
+fn lazy_full_match() {
+    let re = Regex::new(r"a+?").unwrap();
+    assert!(re.is_full_match("aaa"));
+
+    let re = Regex::new(r"(a+?)(a*)").unwrap();
+    let caps = re.captures("aaa").unwrap();
+    assert_eq!(&caps[1], "a");
+    assert_eq!(&caps[2], "aa");
+}
+
+#[test]
+fn lazy_captures() {
+    let re = Regex::new(r"(.+?)=(.*)").unwrap();
+    let caps = re.captures("a=b=c").unwrap();
+    assert_eq!(&caps[1], "a");
+    assert_eq!(&caps[2], "b=c");
+
+    let re = Regex::new(r"(.+)=(.*)").unwrap();
+    let caps = re.captures("a=b=c").unwrap();
+    assert_eq!(&caps[1], "a=b");
+    assert_eq!(&caps[2], "c");
+}
+
+#[test]
+fn leftmost_first() {
+    // 同じ位置から始まるマッチのうち、パターン中で先に書かれた選択肢が優先される
+    let re = Regex::new(r"a|ab").unwrap();
+    assert_eq!(re.find("xab").unwrap().as_str(), "a");
+
+    let re = Regex::new(r"ab|a").unwrap();
+    assert_eq!(re.find("xab").unwrap().as_str(), "ab");
+
+    let re = Regex::new(r"(?m)^(a|ab)").unwrap();
+    assert_eq!(re.find("c\nab").unwrap().as_str(), "a");
+
+    let re = Regex::new(r"(?m)^a+?").unwrap();
+    assert_eq!(re.find("b\naaa").unwrap().range(), 2..3);
+}
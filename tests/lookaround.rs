@@ -0,0 +1,129 @@
+use regex::{Engine, ErrorKind, Regex, RegexBuilder};
+
+#[test]
+fn lookahead() {
+    let re = Regex::new(r"\w+(?=!)").unwrap();
+    assert_eq!(re.find("hi there!").unwrap().as_str(), "there");
+    assert!(!re.is_match("hi there"));
+
+    let re = Regex::new(r"\b\d+\b(?!%)").unwrap();
+    assert_eq!(re.find("50% of 20").unwrap().as_str(), "20");
+
+    // 先読みは文字を消費しない
+    let re = Regex::new(r"(?=\w*\d)(?=\w*[A-Z])\w{8,}").unwrap();
+    assert!(re.is_full_match("Passw0rdX"));
+    assert!(!re.is_full_match("password1"));
+}
+
+#[test]
+fn lookbehind() {
+    let re = Regex::new(r"(?<!\$)\b\d+").unwrap();
+    assert_eq!(re.find("$100 and 200").unwrap().as_str(), "200");
+
+    let re = Regex::new(r"(?<=\$)\d+").unwrap();
+    assert_eq!(re.find("100 and $200").unwrap().range(), 9..12);
+
+    // 可変長の後読み
+    let re = Regex::new(r"(?<=^|,\s*)\w+").unwrap();
+    let caps: Vec<_> = ["a", "b,  c"]
+        .iter()
+        .map(|text| re.find(text).unwrap().as_str())
+        .collect();
+    assert_eq!(caps, ["a", "b"]);
+
+    let re = Regex::new(r"(?<=é)x").unwrap();
+    assert_eq!(re.find("xéx").unwrap().range(), 3..4);
+
+    let re = Regex::new(r"(?<=ab)c").unwrap();
+    assert_eq!(re.find("bacabc").unwrap().range(), 5..6);
+
+    let re = Regex::new(r"(?<=a(?i)b)c").unwrap();
+    assert!(re.is_match("aBc"));
+    assert!(!re.is_match("Abc"));
+
+    // 後読みの中の後方参照も後ろから照合する
+    let re = Regex::new(r"(\w)\w(?<=\1\1)").unwrap();
+    assert_eq!(re.find("abcc").unwrap().range(), 2..4);
+}
+
+#[test]
+fn lookbehind_long_haystack() {
+    let haystack = format!("{}5", "x".repeat(10_000));
+    let re = Regex::new(r"(?<!\$)\d+").unwrap();
+    assert!(re.is_match(&haystack));
+    assert_eq!(re.find(&haystack).unwrap().range(), 10_000..10_001);
+
+    // 可変長の後読みは直前の文字から必要な分だけ読む
+    let haystack = format!("{},5", "x".repeat(10_000));
+    let re = Regex::new(r"(?<=,\s*)\d").unwrap();
+    assert_eq!(re.find(&haystack).unwrap().range(), 10_001..10_002);
+}
+
+#[test]
+fn lookaround_captures() {
+    let re = Regex::new(r"(?=(\w+))\w").unwrap();
+    let caps = re.captures("  abc").unwrap();
+    assert_eq!(&caps[0], "a");
+    assert_eq!(&caps[1], "abc");
+
+    // 否定の先読みの中のグループは記録されない
+    let re = Regex::new(r"(?!(x))(\w)").unwrap();
+    let caps = re.captures("xa").unwrap();
+    assert_eq!(&caps[2], "a");
+    assert!(caps.get(1).is_none());
+
+    let re = Regex::new(r"(?<=(\w)(\d))x").unwrap();
+    let caps = re.captures("ab1x").unwrap();
+    assert_eq!(caps[0], *"x");
+    assert_eq!(caps.get(1).unwrap().range(), 1..2);
+    assert_eq!(caps.get(2).unwrap().range(), 2..3);
+}
+
+#[test]
+fn lookaround_flags() {
+    let re = Regex::new(r"a(?=(?i)b)b").unwrap();
+    assert!(re.is_full_match("ab"));
+    assert!(!re.is_match("aB"));
+}
+
+#[test]
+fn engine_errors() {
+    let pattern = r"a(?=b)";
+    let build = |engine| RegexBuilder::new(pattern).engine(engine).build();
+
+    for engine in [Engine::Dfa, Engine::PikeVM] {
+        let err = build(engine).err().unwrap();
+        assert!(matches!(err.kind(), ErrorKind::Unsupported(_)), "{:?}", err);
+    }
+    assert!(build(Engine::Backtrack).unwrap().is_match("ab"));
+}
+
+#[test]
+fn engines_agree() {
+    let patterns = [
+        r"(a|ab)(c|bcd)(d*)",
+        r"(?m)^\w+$",
+        r"\bfoo\b",
+        r"x*?y",
+        r"a\Z",
+    ];
+    let haystacks = ["abcd", "abcdd", "foo\nbar", "a foo b", "xxy", "a\n", ""];
+    for pattern in patterns {
+        let engines = [Engine::Dfa, Engine::PikeVM, Engine::Backtrack]
+            .map(|engine| RegexBuilder::new(pattern).engine(engine).build().unwrap());
+        for haystack in haystacks {
+            let results: Vec<_> = engines
+                .iter()
+                .map(|re| {
+                    let groups: Vec<_> = re
+                        .captures(haystack)
+                        .map(|caps| caps.iter().map(|m| m.map(|m| m.range())).collect())
+                        .unwrap_or_default();
+                    (re.is_match(haystack), re.is_full_match(haystack), groups)
+                })
+                .collect();
+            assert_eq!(results[0], results[1], "{} {:?}", pattern, haystack);
+            assert_eq!(results[0], results[2], "{} {:?}", pattern, haystack);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use regex::{Regex, RegexBuilder};
+
+#[test]
+fn builder_multi_line() {
+    let re = RegexBuilder::new(r"^b$").multi_line(true).build().unwrap();
+    assert!(re.is_match("a\nb\nc"));
+    assert_eq!(re.find("a\nb\nc").unwrap().range(), 2..3);
+
+    let re = RegexBuilder::new(r"^b$").build().unwrap();
+    assert!(!re.is_match("a\nb\nc"));
+}
+
+#[test]
+fn multi_line_anchors() {
+    let re = Regex::new(r"(?m)^\w+$").unwrap();
+    assert_eq!(re.find("  \nfoo\nbar").unwrap().as_str(), "foo");
+    assert!(re.is_full_match("foo"));
+    assert!(!re.is_full_match("foo\n"));
+
+    // 空行にもマッチする
+    let re = Regex::new(r"(?m)^$").unwrap();
+    assert_eq!(re.find("a\n\nb").unwrap().range(), 2..2);
+    assert!(re.is_match(""));
+    assert!(!re.is_match("ab"));
+
+    let re = Regex::new(r"(?m)a$\n^b").unwrap();
+    assert!(re.is_match("xa\nby"));
+}
+
+#[test]
+fn crlf_mode() {
+    let re = Regex::new(r"(?mR)^\w+$").unwrap();
+    assert_eq!(re.find("  \r\nfoo\r\nbar").unwrap().as_str(), "foo");
+    assert_eq!(re.find("  \rfoo\n").unwrap().as_str(), "foo");
+
+    // \r と \n の間では成立しない
+    let re = Regex::new(r"(?mR)^$").unwrap();
+    assert!(!re.is_match("a\r\nb"));
+    assert_eq!(re.find("a\r\n\r\nb").unwrap().range(), 3..3);
+
+    let re = Regex::new(r"(?mR)$").unwrap();
+    assert_eq!(re.find("ab\r\n").unwrap().range(), 2..2);
+
+    // CRLF モードでなければ \r は行末ではない
+    let re = Regex::new(r"(?m)^\w+$").unwrap();
+    assert!(!re.is_match("foo\r\n"));
+    assert_eq!(re.find("foo\r\nbar").unwrap().as_str(), "bar");
+}
+
+#[test]
+fn builder_crlf() {
+    let re = RegexBuilder::new(r"^b$")
+        .multi_line(true)
+        .crlf(true)
+        .build()
+        .unwrap();
+    assert_eq!(re.find("a\r\nb\r\n").unwrap().range(), 3..4);
+
+    // 複数行モードでなければ ^ と $ はテキストの端のみを表す
+    let re = RegexBuilder::new(r"^b$").crlf(true).build().unwrap();
+    assert!(!re.is_match("a\r\nb\r\n"));
+}
+
+#[test]
+fn crlf_dot() {
+    let re = Regex::new(r"(?R).+").unwrap();
+    assert_eq!(re.find("ab\r\ncd").unwrap().as_str(), "ab");
+
+    let re = Regex::new(r"(?Rs).+").unwrap();
+    assert_eq!(re.find("ab\r\ncd").unwrap().as_str(), "ab\r\ncd");
+}
+
+#[test]
+fn multi_line_with_captures() {
+    let re = Regex::new(r"(?m)^(\w+): (.*)$").unwrap();
+    let caps = re.captures("# log\nerror: disk full\ninfo: ok").unwrap();
+    assert_eq!(&caps[1], "error");
+    assert_eq!(&caps[2], "disk full");
+}
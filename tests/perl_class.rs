@@ -0,0 +1,63 @@
+use regex::{Regex, RegexBuilder};
+
+#[test]
+fn perl_digit() {
+    let re = Regex::new(r"\d+").unwrap();
+    assert_eq!(re.find("abc 123 def").unwrap().as_str(), "123");
+    assert!(re.is_full_match("٣٤٥"));
+
+    let re = Regex::new(r"\D+").unwrap();
+    assert_eq!(re.find("12ab34").unwrap().as_str(), "ab");
+}
+
+#[test]
+fn perl_word() {
+    let re = Regex::new(r"\w+").unwrap();
+    assert_eq!(re.find("  foo_bar1 baz").unwrap().as_str(), "foo_bar1");
+    assert!(re.is_full_match("日本語ですé"));
+
+    let re = Regex::new(r"\W").unwrap();
+    assert_eq!(re.find("ab-c").unwrap().as_str(), "-");
+    assert!(!re.is_match("abc_123"));
+}
+
+#[test]
+fn perl_space() {
+    let re = Regex::new(r"a\s+b").unwrap();
+    assert!(re.is_full_match("a \t\r\nb"));
+    assert!(re.is_full_match("a\u{3000}b"));
+
+    let re = Regex::new(r"\S+").unwrap();
+    assert_eq!(re.find("  xy z").unwrap().as_str(), "xy");
+}
+
+#[test]
+fn perl_class_in_bracket() {
+    let re = Regex::new(r"[\d_]+").unwrap();
+    assert_eq!(re.find("ab1_2c").unwrap().as_str(), "1_2");
+
+    let re = Regex::new(r"[^\s,]+").unwrap();
+    assert_eq!(re.find(" , abc,def").unwrap().as_str(), "abc");
+
+    let re = Regex::new(r"[\w-]+").unwrap();
+    assert!(re.is_full_match("foo-bar"));
+
+    let re = Regex::new(r"[^\D]").unwrap();
+    assert!(re.is_full_match("5"));
+    assert!(!re.is_full_match("a"));
+}
+
+#[test]
+fn perl_class_ascii() {
+    let re = Regex::new(r"(?-u)\d").unwrap();
+    assert!(re.is_full_match("7"));
+    assert!(!re.is_full_match("٣"));
+
+    let re = Regex::new(r"(?-u:\w+)\w").unwrap();
+    assert!(re.is_full_match("abcé"));
+    assert!(!re.is_full_match("éabc"));
+
+    let re = RegexBuilder::new(r"\s\W").unicode(false).build().unwrap();
+    assert!(re.is_full_match(" é"));
+    assert!(!re.is_full_match("\u{3000}-"));
+}
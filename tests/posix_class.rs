@@ -0,0 +1,53 @@
+use regex::Regex;
+
+#[test]
+fn posix_classes() {
+    let cases = [
+        ("alnum", "aZ09", "_"),
+        ("alpha", "azAZ", "1"),
+        ("ascii", "\0~\x7F", "é"),
+        ("blank", " \t", "\n"),
+        ("cntrl", "\0\x1F\x7F", " "),
+        ("digit", "0123456789", "a"),
+        ("graph", "!a~", " "),
+        ("lower", "az", "A"),
+        ("print", " a~", "\t"),
+        ("punct", "!/:@[`{~", "a"),
+        ("space", " \t\n\x0B\x0C\r", "a"),
+        ("upper", "AZ", "a"),
+        ("word", "aZ0_", "-"),
+        ("xdigit", "09afAF", "g"),
+    ];
+
+    for (name, matched, unmatched) in cases {
+        let re = Regex::new(&format!("[[:{}:]]+", name)).unwrap();
+        assert!(re.is_full_match(matched), "{}", name);
+        assert!(!re.is_match(unmatched), "{}", name);
+
+        let re = Regex::new(&format!("[[:^{}:]]", name)).unwrap();
+        assert!(re.is_full_match(unmatched), "{}", name);
+    }
+}
+
+#[test]
+fn posix_class_combined() {
+    let re = Regex::new(r"[[:upper:][:digit:]_]+").unwrap();
+    assert_eq!(re.find("abC_9d").unwrap().as_str(), "C_9");
+
+    let re = Regex::new(r"[^[:space:][:punct:]]+").unwrap();
+    assert_eq!(re.find("  , hello!").unwrap().as_str(), "hello");
+}
+
+#[test]
+fn posix_class_outside_bracket() {
+    // 文字クラスの外の [:alpha:] は通常の文字クラスになる
+    let re = Regex::new(r"[:alpha:]+").unwrap();
+    assert!(re.is_full_match("ahpla:"));
+}
+
+#[test]
+fn posix_class_errors() {
+    for pattern in [r"[[:foo:]]", r"[[:alpha:]"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
@@ -0,0 +1,136 @@
+use regex::{Engine, ErrorKind, Regex, RegexBuilder};
+
+#[test]
+fn repetition_exact() {
+    let re = Regex::new(r"a{3}").unwrap();
+    assert!(re.is_full_match("aaa"));
+    assert!(!re.is_full_match("aa"));
+    assert!(!re.is_full_match("aaaa"));
+
+    let re = Regex::new(r"[0-9]{3}-[0-9]{4}").unwrap();
+    assert_eq!(re.find("tel: 012-3456-789").unwrap().as_str(), "012-3456");
+
+    let re = Regex::new(r"xa{0}y").unwrap();
+    assert!(re.is_full_match("xy"));
+}
+
+#[test]
+fn repetition_range() {
+    let re = Regex::new(r"a{2,4}").unwrap();
+    assert!(!re.is_full_match("a"));
+    assert!(re.is_full_match("aa"));
+    assert!(re.is_full_match("aaaa"));
+    assert!(!re.is_full_match("aaaaa"));
+    assert_eq!(re.find("baaaaaa").unwrap().range(), 1..5);
+
+    let re = Regex::new(r"(ab){2,}").unwrap();
+    assert!(!re.is_full_match("ab"));
+    assert!(re.is_full_match("ababab"));
+    let caps = re.captures("xabababx").unwrap();
+    assert_eq!(&caps[0], "ababab");
+    assert_eq!(&caps[1], "ab");
+}
+
+#[test]
+fn repetition_stacked() {
+    let re = Regex::new(r"(a{2}){3}").unwrap();
+    assert!(re.is_full_match("aaaaaa"));
+    assert!(!re.is_full_match("aaaaa"));
+
+    let re = Regex::new(r"a{2}{3}").unwrap();
+    assert!(re.is_full_match("aaaaaa"));
+}
+
+#[test]
+fn repetition_literal_brace() {
+    // 形式に合わない { は文字として扱う
+    for pattern in [r"a{,2}", r"x{y}", r"a{1"] {
+        let re = Regex::new(pattern).unwrap();
+        assert!(re.is_full_match(pattern), "{}", pattern);
+    }
+
+    let re = Regex::new(r"[{}]+").unwrap();
+    assert!(re.is_full_match("{}{"));
+}
+
+#[test]
+fn repetition_errors() {
+    for pattern in [r"a{3,2}", r"{2}", r"a|{1,}"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
+
+#[test]
+fn repetition_size_limit() {
+    let error = Regex::new(r"a{1000}{1000}").err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::SizeLimitExceeded { .. }));
+
+    let error = RegexBuilder::new(r"[a-z]{100}")
+        .size_limit(100)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), &ErrorKind::SizeLimitExceeded { limit: 100 });
+
+    assert!(RegexBuilder::new(r"[a-z]{10}")
+        .size_limit(100)
+        .build()
+        .is_ok());
+
+    // 繰り返しで複製される文字クラスの範囲も大きさに数える
+    let error = RegexBuilder::new(r"\w{10}")
+        .size_limit(1_000)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), &ErrorKind::SizeLimitExceeded { limit: 1_000 });
+    let error = Regex::new(r"\w{1000}{10}").err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::SizeLimitExceeded { .. }));
+    assert!(RegexBuilder::new(r"[a-z]{1000}{10}")
+        .engine(Engine::PikeVM)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn dfa_size_limit() {
+    // DFAの状態数が指数的に増えるパターンは Pike VM で照合する
+    for pattern in [r"(a|b)*a(a|b){20}", r"\w*a\w{20}"] {
+        let haystack = format!("{}{}", "a".repeat(20), "b".repeat(100));
+        let re = Regex::new(pattern).unwrap();
+        assert!(re.is_match(&haystack), "{}", pattern);
+        assert!(!re.is_match("b"), "{}", pattern);
+
+        let error = RegexBuilder::new(pattern)
+            .engine(Engine::Dfa)
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            matches!(error.kind(), ErrorKind::DfaSizeLimitExceeded { .. }),
+            "{}",
+            pattern
+        );
+    }
+
+    // Unicode の文字クラスを含んでいても、区別の必要な文字の分類が少なければDFAは小さい
+    for pattern in [
+        r"\w{100}",
+        r"\Z{2}?\z[ab]+?|(?i:.\w{2}?\<*|)+[^a]",
+        r"[\p{L}\p{N}]{20}",
+    ] {
+        let re = RegexBuilder::new(pattern).engine(Engine::Dfa).build();
+        assert!(re.is_ok(), "{}", pattern);
+    }
+
+    let build = |engine| {
+        RegexBuilder::new(r"[a-c]x|y")
+            .dfa_size_limit(1)
+            .engine(engine)
+            .build()
+    };
+    let error = build(Engine::Dfa).err().unwrap();
+    assert_eq!(error.kind(), &ErrorKind::DfaSizeLimitExceeded { limit: 1 });
+    let re = build(Engine::Automatic).unwrap();
+    assert_eq!(re.find("zbxy").unwrap().range(), 1..3);
+}
@@ -0,0 +1,87 @@
+use regex::{Engine, Regex, RegexBuilder};
+
+#[test]
+fn start_and_end_of_text() {
+    let re = Regex::new(r"\Afoo\z").unwrap();
+    assert!(re.is_match("foo"));
+    assert!(!re.is_match("foo\n"));
+    assert!(!re.is_match("xfoo"));
+
+    // 複数行モードでも行頭・行末にはマッチしない
+    let re = Regex::new(r"(?m)\Ab").unwrap();
+    assert!(!re.is_match("a\nb"));
+    let re = Regex::new(r"(?m)a\z").unwrap();
+    assert!(!re.is_match("a\nb"));
+    assert_eq!(re.find("b\na").unwrap().range(), 2..3);
+
+    let re = Regex::new(r"(?m)\A^\w+$").unwrap();
+    assert_eq!(re.find("foo\nbar").unwrap().as_str(), "foo");
+}
+
+#[test]
+fn end_of_text_optional_newline() {
+    let re = Regex::new(r"a\Z").unwrap();
+    assert!(re.is_match("a"));
+    assert!(re.is_match("a\n"));
+    assert!(!re.is_match("a\n\n"));
+    assert!(!re.is_match("a\nb"));
+    assert_eq!(re.find("ba\n").unwrap().range(), 1..2);
+    assert!(!re.is_full_match("a\n"));
+
+    let re = Regex::new(r"a\Z\n").unwrap();
+    assert!(re.is_full_match("a\n"));
+    assert!(!re.is_match("a\n\n"));
+
+    let re = Regex::new(r"(?m)\w+\Z").unwrap();
+    assert_eq!(re.find("foo\nbar\n").unwrap().as_str(), "bar");
+
+    let re = Regex::new(r"\Z").unwrap();
+    assert_eq!(re.find("ab\n").unwrap().range(), 2..2);
+    assert_eq!(re.find("").unwrap().range(), 0..0);
+}
+
+#[test]
+fn text_anchor_captures() {
+    let re = Regex::new(r"\A(\w+) (\w+)\Z").unwrap();
+    let caps = re.captures("hello world\n").unwrap();
+    assert_eq!(&caps[1], "hello");
+    assert_eq!(&caps[2], "world");
+    assert!(re.captures("hello world\n\n").is_none());
+}
+
+#[test]
+fn end_of_text_optional_newline_with_other_assertions() {
+    let patterns = [
+        r"\Z^",
+        r"(?m)\Z^",
+        r"^\Z",
+        r"$\Z",
+        r"(?m)\Z$",
+        r"\Z\b",
+        r"\b\Z",
+        r"\B\Z",
+        r"a\Z\b",
+        r"(?m)^\Z",
+        r"(?Rm)\Z^",
+    ];
+    let haystacks = ["\n", "", "a", "a\n", "\n\n", "a\nb\n", "ab\r\n", " \n"];
+    for pattern in patterns {
+        let [dfa, pikevm] = [Engine::Dfa, Engine::PikeVM]
+            .map(|engine| RegexBuilder::new(pattern).engine(engine).build().unwrap());
+        for haystack in haystacks {
+            let results = [&dfa, &pikevm].map(|re| {
+                (
+                    re.is_match(haystack),
+                    re.is_full_match(haystack),
+                    re.find(haystack).map(|m| m.range()),
+                )
+            });
+            assert_eq!(results[0], results[1], "{} {:?}", pattern, haystack);
+        }
+    }
+
+    let re = Regex::new(r"\Z^").unwrap();
+    assert_eq!(re.find("\n").unwrap().range(), 0..0);
+    let re = Regex::new(r"(?m)\Z^").unwrap();
+    assert_eq!(re.find("\n").unwrap().range(), 0..0);
+}
@@ -0,0 +1,71 @@
+use regex::{ErrorKind, Regex};
+
+#[test]
+fn general_category() {
+    let re = Regex::new(r"\pL+").unwrap();
+    assert_eq!(re.find("123 héllo wörld").unwrap().as_str(), "héllo");
+
+    let re = Regex::new(r"\p{Lu}\p{Ll}+").unwrap();
+    assert_eq!(re.find("abc Δέλτα Hello").unwrap().as_str(), "Δέλτα");
+
+    let re = Regex::new(r"\p{Uppercase_Letter}").unwrap();
+    assert!(re.is_full_match("Σ"));
+    assert!(!re.is_full_match("σ"));
+
+    let re = Regex::new(r"\p{gc=Nd}+").unwrap();
+    assert!(re.is_full_match("42٣"));
+}
+
+#[test]
+fn script() {
+    let re = Regex::new(r"\p{Greek}+").unwrap();
+    assert_eq!(re.find("alpha: αβγ").unwrap().as_str(), "αβγ");
+
+    let re = Regex::new(r"\p{sc=Hiragana}+\p{Script=Han}").unwrap();
+    assert!(re.is_full_match("ひらがな漢"));
+
+    let re = Regex::new(r"\p{ grek }").unwrap();
+    assert!(re.is_full_match("Ω"));
+}
+
+#[test]
+fn binary_property() {
+    let re = Regex::new(r"\p{Emoji_Presentation}").unwrap();
+    assert!(re.is_full_match("😀"));
+    assert!(!re.is_full_match("a"));
+
+    let re = Regex::new(r"\p{White_Space}\p{Alphabetic}\p{ASCII}").unwrap();
+    assert!(re.is_full_match(" éa"));
+}
+
+#[test]
+fn negated() {
+    let re = Regex::new(r"\PL+").unwrap();
+    assert_eq!(re.find("ab12 cd").unwrap().as_str(), "12 ");
+
+    let re = Regex::new(r"[\p{Greek}\d]+").unwrap();
+    assert_eq!(re.find("x αβ12 y").unwrap().as_str(), "αβ12");
+
+    let re = Regex::new(r"[^\P{Greek}]").unwrap();
+    assert!(re.is_full_match("α"));
+    assert!(!re.is_full_match("a"));
+}
+
+#[test]
+fn unknown_property() {
+    let patterns = [r"\p{Klingon}", r"ab\p{sc=Elvish}"];
+
+    let error = Regex::new(patterns[0]).err().unwrap();
+    assert_eq!(
+        error.kind(),
+        &ErrorKind::UnknownPropertyName("Klingon".to_string())
+    );
+    assert_eq!(error.position(), Some(0));
+
+    let error = Regex::new(patterns[1]).err().unwrap();
+    assert_eq!(
+        error.kind(),
+        &ErrorKind::UnknownPropertyValue("Elvish".to_string())
+    );
+    assert_eq!(error.position(), Some(2));
+}
@@ -0,0 +1,95 @@
+use regex::Regex;
+
+#[test]
+fn word_boundary() {
+    let re = Regex::new(r"\bfoo\b").unwrap();
+    assert!(re.is_match("foo"));
+    assert!(re.is_match("a foo."));
+    assert!(!re.is_match("foobar"));
+    assert!(!re.is_match("_foo"));
+    assert_eq!(re.find("foobar foo").unwrap().range(), 7..10);
+    assert!(re.is_full_match("foo"));
+
+    let re = Regex::new(r"\b").unwrap();
+    assert!(re.is_match("a"));
+    assert!(!re.is_match(""));
+    assert!(!re.is_match(" "));
+}
+
+#[test]
+fn not_word_boundary() {
+    let re = Regex::new(r"\Boo\B").unwrap();
+    assert!(re.is_match("foot"));
+    assert!(!re.is_match("foo"));
+    assert!(!re.is_match("oo"));
+    assert_eq!(re.find("oo foot").unwrap().range(), 4..6);
+
+    let re = Regex::new(r"\B").unwrap();
+    assert!(re.is_match(""));
+    assert!(re.is_match("ab"));
+    assert!(!re.is_match("a"));
+}
+
+#[test]
+fn word_start_and_end() {
+    for pattern in [r"\<\w+\>", r"\b{start}\w+\b{end}"] {
+        let re = Regex::new(pattern).unwrap();
+        assert_eq!(re.find(", cd_e, f").unwrap().range(), 2..6);
+        assert!(re.is_full_match("cd_e"));
+    }
+
+    let re = Regex::new(r"\<").unwrap();
+    assert_eq!(re.find(" cd").unwrap().range(), 1..1);
+    assert!(!re.is_match(" "));
+    let re = Regex::new(r"\>").unwrap();
+    assert_eq!(re.find("ab cd").unwrap().range(), 2..2);
+
+    let re = Regex::new(r"\<d|c\>|cd").unwrap();
+    assert_eq!(re.find("acd").unwrap().range(), 1..3);
+
+    // 文字クラスの中では文字として扱う
+    let re = Regex::new(r"[\<\>]+").unwrap();
+    assert!(re.is_full_match("<>"));
+}
+
+#[test]
+fn identifiers() {
+    let re = Regex::new(r"\b[A-Za-z_]\w*\b").unwrap();
+    assert_eq!(re.find("(1x + 2, bar_2)").unwrap().as_str(), "bar_2");
+
+    let re = Regex::new(r"\bfoo\w*\(").unwrap();
+    assert_eq!(re.find("xfoo(); foo_bar();").unwrap().as_str(), "foo_bar(");
+}
+
+#[test]
+fn unicode_word_boundary() {
+    let re = Regex::new(r"\bé\b").unwrap();
+    assert!(re.is_match("café é"));
+    assert_eq!(re.find("café é").unwrap().range(), 6..8);
+
+    // ASCII モードでは é は単語構成文字ではない
+    let re = Regex::new(r"(?-u)\bé").unwrap();
+    assert!(!re.is_match(" é"));
+    assert!(re.is_match("aé"));
+
+    let re = Regex::new(r"(?-u:\b)x(?u:\b)").unwrap();
+    assert!(re.is_match("éx"));
+    assert!(!re.is_match("xé"));
+}
+
+#[test]
+fn word_boundary_with_anchors() {
+    let re = Regex::new(r"^\w+\b").unwrap();
+    assert_eq!(re.find("ab cd").unwrap().range(), 0..2);
+
+    let re = Regex::new(r"\b\w+$").unwrap();
+    assert_eq!(re.find("ab cd").unwrap().range(), 3..5);
+    assert!(!re.is_match("ab "));
+}
+
+#[test]
+fn word_boundary_errors() {
+    for pattern in [r"\b{foo}", r"[\b]"] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}